@@ -0,0 +1,158 @@
+//! Cost of the JNI lookups cached by `src/cache.rs`, on a desktop JVM
+//!
+//! Compares resolving classes and methods by name on every call, as the crate used to, with
+//! the crate's own wrappers, which resolve them once through `cache::Class` and `cache::Method`
+//! and then call the unchecked entry points. The AndroidKeyStore provider only exists on
+//! Android, so the alias walk of `AndroidKeyStore::aliases()` runs over the `Enumeration` of a
+//! `java.util.Vector`, and the key calls use `SecretKey`, which needs no provider.
+//!
+//! Needs the `desktop-jvm` feature and `libjvm` at link and run time:
+//!
+//! ```text
+//! JVM=$JAVA_HOME/lib/server
+//! RUSTFLAGS="-L $JVM" LD_LIBRARY_PATH=$JVM cargo bench --features desktop-jvm --bench jni_lookups
+//! ```
+//!
+//! The speedups depend on the JVM and the machine, run the bench rather than trusting a number.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use android_keystore::{Object, SecretKey, SecretKeyAlgorithm, bench::iter_enumeration};
+use jni::{
+    JNIEnv,
+    objects::{JObject, JString, JValue},
+};
+
+const ALIASES: usize = 1000;
+const WALKS: usize = 50;
+const KEYS: usize = 20_000;
+
+/// Average time of `f` over `inputs`, the first one warms up
+fn time<T>(name: &str, inputs: Vec<T>, mut f: impl FnMut(T)) -> Duration {
+    let mut inputs = inputs.into_iter();
+    f(inputs.next().expect("no warm-up input"));
+
+    let count = inputs.len() as u32;
+    let start = Instant::now();
+    inputs.for_each(&mut f);
+    let per_iteration = start.elapsed() / count;
+    println!("{name:<40} {per_iteration:>12.2?}");
+    per_iteration
+}
+
+fn speedup(by_name: Duration, cached: Duration) {
+    println!(
+        "  speedup {:.2}x",
+        by_name.as_secs_f64() / cached.as_secs_f64()
+    );
+}
+
+/// `count` enumerations over the same vector of aliases, built before the timed walks
+fn enumerations<'a>(count: usize, env: &mut JNIEnv<'a>) -> Vec<JObject<'a>> {
+    let vector = env.new_object("java/util/Vector", "()V", &[]).unwrap();
+    for i in 0..ALIASES {
+        let alias = env.new_string(format!("alias-{i}")).unwrap();
+        env.call_method(
+            &vector,
+            "add",
+            "(Ljava/lang/Object;)Z",
+            &[JValue::Object(&alias)],
+        )
+        .unwrap();
+        env.delete_local_ref(alias).unwrap();
+    }
+
+    (0..count)
+        .map(|_| {
+            env.call_method(&vector, "elements", "()Ljava/util/Enumeration;", &[])
+                .unwrap()
+                .l()
+                .unwrap()
+        })
+        .collect()
+}
+
+fn aliases_by_name<'a>(aliases: JObject<'a>, env: &mut JNIEnv<'a>) {
+    let mut res = vec![];
+    loop {
+        let alias = env
+            .with_local_frame(16, |env| {
+                if !env
+                    .call_method(&aliases, "hasMoreElements", "()Z", &[])?
+                    .z()?
+                {
+                    return Ok(None);
+                }
+                let alias: JString = env
+                    .call_method(&aliases, "nextElement", "()Ljava/lang/Object;", &[])?
+                    .l()?
+                    .into();
+                Ok::<_, jni::errors::Error>(Some(String::from(env.get_string(&alias)?)))
+            })
+            .unwrap();
+        match alias {
+            Some(alias) => res.push(alias),
+            None => break,
+        }
+    }
+    env.delete_local_ref(aliases).unwrap();
+    black_box(res);
+}
+
+fn secret_key_by_name(env: &mut JNIEnv<'_>) {
+    let algorithm = env
+        .with_local_frame(16, |env| {
+            let bytes = env.byte_array_from_slice(&[0; 32])?;
+            let algorithm = env.new_string("AES")?;
+            let key = env.new_object(
+                "javax/crypto/spec/SecretKeySpec",
+                "([BLjava/lang/String;)V",
+                &[JValue::Object(&bytes), JValue::Object(&algorithm)],
+            )?;
+            let algorithm: JString = env
+                .call_method(&key, "getAlgorithm", "()Ljava/lang/String;", &[])?
+                .l()?
+                .into();
+            Ok::<_, jni::errors::Error>(String::from(env.get_string(&algorithm)?))
+        })
+        .unwrap();
+    black_box(algorithm);
+}
+
+fn secret_key_cached(env: &mut JNIEnv<'_>) {
+    let key = SecretKey::from_bytes(SecretKeyAlgorithm::Aes, &[0; 32], env).unwrap();
+    black_box(key.get_algorithm(env).unwrap());
+    env.delete_local_ref(key.into_inner()).unwrap();
+}
+
+fn main() {
+    let vm = common::create_vm();
+    let mut env = vm.attach_current_thread().unwrap();
+
+    println!("aliases() on {ALIASES} aliases, per walk");
+    let inputs = enumerations(WALKS + 1, &mut env);
+    let by_name = time("  by name", inputs, |aliases| {
+        aliases_by_name(aliases, &mut env)
+    });
+    let inputs = enumerations(WALKS + 1, &mut env);
+    let cached = time("  android_keystore::Aliases", inputs, |aliases| {
+        let aliases = iter_enumeration(aliases, &mut env).collect::<Result<Vec<_>, _>>();
+        black_box(aliases.unwrap());
+    });
+    speedup(by_name, cached);
+
+    println!("AES SecretKey creation and getAlgorithm(), per key");
+    let by_name = time("  by name", vec![(); KEYS + 1], |()| {
+        secret_key_by_name(&mut env)
+    });
+    let cached = time("  android_keystore::SecretKey", vec![(); KEYS + 1], |()| {
+        secret_key_cached(&mut env)
+    });
+    speedup(by_name, cached);
+}
@@ -0,0 +1,91 @@
+use jni::{
+    JNIEnv,
+    objects::{JObject, JString},
+};
+
+use crate::{ENUMERATION, Error, LOCAL_FRAME_CAPACITY, Result, cache::Method};
+
+/// Lazy iterator over the aliases of a keystore, see [`crate::AndroidKeyStore::iter_aliases`]
+///
+/// Walks the underlying `java.util.Enumeration` one alias at a time. Stops after the first
+/// error.
+pub struct Aliases<'e, 'a> {
+    enumeration: JObject<'a>,
+    env: &'e mut JNIEnv<'a>,
+    done: bool,
+}
+
+impl<'e, 'a> Aliases<'e, 'a> {
+    pub(crate) fn new(enumeration: JObject<'a>, env: &'e mut JNIEnv<'a>) -> Self {
+        Self {
+            enumeration,
+            env,
+            done: false,
+        }
+    }
+
+    /// Only the aliases starting with `prefix`, errors are kept
+    pub fn with_prefix<'p>(
+        self,
+        prefix: &'p str,
+    ) -> impl Iterator<Item = Result<String>> + use<'e, 'a, 'p> {
+        self.matching(move |alias| alias.starts_with(prefix))
+    }
+
+    /// Only the aliases `predicate` accepts, errors are kept
+    pub fn matching<P>(self, mut predicate: P) -> impl Iterator<Item = Result<String>>
+    where
+        P: FnMut(&str) -> bool,
+    {
+        self.filter(move |alias| match alias {
+            Ok(alias) => predicate(alias),
+            Err(_) => true,
+        })
+    }
+
+    /// Only the aliases `regex` matches, errors are kept
+    #[cfg(feature = "regex")]
+    pub fn matching_regex<'r>(
+        self,
+        regex: &'r regex::Regex,
+    ) -> impl Iterator<Item = Result<String>> + use<'e, 'a, 'r> {
+        self.matching(move |alias| regex.is_match(alias))
+    }
+
+    fn next_alias(&mut self) -> Result<Option<String>> {
+        static HAS_MORE_ELEMENTS: Method = Method::new(&ENUMERATION, "hasMoreElements", "()Z");
+        static NEXT_ELEMENT: Method =
+            Method::new(&ENUMERATION, "nextElement", "()Ljava/lang/Object;");
+
+        let enumeration = &self.enumeration;
+        self.env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            if !HAS_MORE_ELEMENTS.call(enumeration, &[], env)?.z()? {
+                return Ok(None);
+            }
+            let alias: JString = NEXT_ELEMENT.call(enumeration, &[], env)?.l()?.into();
+
+            Ok::<_, Error>(Some(env.get_string(&alias)?.into()))
+        })
+    }
+}
+
+impl Iterator for Aliases<'_, '_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let res = self.next_alias();
+        self.done = !matches!(res, Ok(Some(_)));
+        res.transpose()
+    }
+}
+
+impl Drop for Aliases<'_, '_> {
+    fn drop(&mut self) {
+        let enumeration = std::mem::replace(&mut self.enumeration, JObject::null());
+        let _ = self.env.delete_local_ref(enumeration);
+    }
+}
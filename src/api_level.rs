@@ -0,0 +1,42 @@
+use std::sync::OnceLock;
+
+use jni::JNIEnv;
+
+use crate::{Error, Result, cache::Class, exception::Catch};
+
+static BUILD_VERSION: Class = Class::new("android/os/Build$VERSION");
+static API_LEVEL: OnceLock<u32> = OnceLock::new();
+
+pub const HONEYCOMB: u32 = 11;
+pub const M: u32 = 23;
+pub const P: u32 = 28;
+pub const R: u32 = 30;
+pub const S: u32 = 31;
+pub const TIRAMISU: u32 = 33;
+pub const UPSIDE_DOWN_CAKE: u32 = 34;
+
+/// API level of the running Android platform, `Build.VERSION.SDK_INT`
+///
+/// Read once per process, then served from a cache.
+pub fn api_level(env: &mut JNIEnv<'_>) -> Result<u32> {
+    if let Some(level) = API_LEVEL.get() {
+        return Ok(*level);
+    }
+
+    let class = BUILD_VERSION.get(env)?;
+    let level = env
+        .get_static_field(class, "SDK_INT", "I")
+        .catch(env)?
+        .i()?;
+
+    Ok(*API_LEVEL.get_or_init(|| level as u32))
+}
+
+/// Fails with [`Error::Unsupported`] when running below API level `required`
+pub(crate) fn require(required: u32, env: &mut JNIEnv<'_>) -> Result<()> {
+    let actual = api_level(env)?;
+    match actual >= required {
+        true => Ok(()),
+        false => Err(Error::Unsupported { required, actual }),
+    }
+}
@@ -0,0 +1,212 @@
+//! Process-wide cache of the classes and method IDs used by the wrappers
+//!
+//! Looking up a class and resolving a method by name and signature on every call is slow,
+//! so each wrapper declares its classes and methods as statics that are resolved on first use
+//! and then called through the unchecked JNI entry points. `benches/jni_lookups.rs` measures
+//! the difference.
+
+use std::sync::OnceLock;
+
+use jni::{
+    JNIEnv,
+    objects::{GlobalRef, JClass, JMethodID, JObject, JStaticMethodID, JValue, JValueOwned},
+    signature::{JavaType, TypeSignature},
+};
+
+use crate::{Error, Result, exception::Catch};
+
+/// A Java class, loaded once and kept alive by a global reference
+pub(crate) struct Class {
+    path: &'static str,
+    class: OnceLock<GlobalRef>,
+}
+
+impl Class {
+    pub(crate) const fn new(path: &'static str) -> Self {
+        Self {
+            path,
+            class: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn get(&self, env: &mut JNIEnv<'_>) -> Result<&JClass<'static>> {
+        if let Some(class) = self.class.get() {
+            return Ok(class.as_obj().into());
+        }
+
+        let local = env.find_class(self.path).catch(env)?;
+        let class = env.new_global_ref(&local)?;
+        env.delete_local_ref(local)?;
+
+        Ok(self.class.get_or_init(|| class).as_obj().into())
+    }
+
+    /// A new local reference to the class
+    pub(crate) fn local<'a>(&self, env: &mut JNIEnv<'a>) -> Result<JClass<'a>> {
+        let class = self.get(env)?;
+        Ok(env.new_local_ref(class)?.into())
+    }
+}
+
+/// Checks that `args` match the argument types of `sig`, as the unchecked calls cannot
+fn check_args(sig: &TypeSignature, args: &[JValue]) -> Result<()> {
+    let matches = sig.args.len() == args.len()
+        && sig
+            .args
+            .iter()
+            .zip(args)
+            .all(|(expected, actual)| match expected {
+                JavaType::Primitive(p) => actual.primitive_type() == Some(*p),
+                _ => actual.primitive_type().is_none(),
+            });
+
+    match matches {
+        true => Ok(()),
+        false => Err(Error::Jni(jni::errors::Error::InvalidArgList(sig.clone()))),
+    }
+}
+
+/// An instance method, resolved once
+pub(crate) struct Method {
+    class: &'static Class,
+    name: &'static str,
+    sig: &'static str,
+    id: OnceLock<(JMethodID, TypeSignature)>,
+}
+
+impl Method {
+    pub(crate) const fn new(class: &'static Class, name: &'static str, sig: &'static str) -> Self {
+        Self {
+            class,
+            name,
+            sig,
+            id: OnceLock::new(),
+        }
+    }
+
+    fn id(&self, env: &mut JNIEnv<'_>) -> Result<&(JMethodID, TypeSignature)> {
+        if let Some(id) = self.id.get() {
+            return Ok(id);
+        }
+
+        let sig = TypeSignature::from_str(self.sig)?;
+        let class = self.class.get(env)?;
+        let id = env.get_method_id(class, self.name, self.sig).catch(env)?;
+
+        Ok(self.id.get_or_init(|| (id, sig)))
+    }
+
+    /// Calls the method on `obj`, which must be an instance of the method's class
+    pub(crate) fn call<'a>(
+        &self,
+        obj: &JObject<'_>,
+        args: &[JValue],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<JValueOwned<'a>> {
+        if obj.is_null() {
+            return Err(Error::Jni(jni::errors::Error::NullPtr(self.name)));
+        }
+        let (id, sig) = self.id(env)?;
+        check_args(sig, args)?;
+        let args: Vec<_> = args.iter().map(JValue::as_jni).collect();
+
+        // SAFETY: the method ID was resolved from `sig`, whose return type is passed along
+        // and whose argument types were checked above. `obj` is an instance of the method's
+        // class: the wrappers only call methods of their own class on the object they wrap,
+        // and a wrapper can only be built by `Cast::try_cast`, which checks the class, or
+        // inside the crate from the return value of a method declared to return that class.
+        unsafe { env.call_method_unchecked(obj, *id, sig.ret.clone(), &args) }.catch(env)
+    }
+}
+
+/// A static method, resolved once
+pub(crate) struct StaticMethod {
+    class: &'static Class,
+    name: &'static str,
+    sig: &'static str,
+    id: OnceLock<(JStaticMethodID, TypeSignature)>,
+}
+
+impl StaticMethod {
+    pub(crate) const fn new(class: &'static Class, name: &'static str, sig: &'static str) -> Self {
+        Self {
+            class,
+            name,
+            sig,
+            id: OnceLock::new(),
+        }
+    }
+
+    fn id(&self, env: &mut JNIEnv<'_>) -> Result<&(JStaticMethodID, TypeSignature)> {
+        if let Some(id) = self.id.get() {
+            return Ok(id);
+        }
+
+        let sig = TypeSignature::from_str(self.sig)?;
+        let class = self.class.get(env)?;
+        let id = env
+            .get_static_method_id(class, self.name, self.sig)
+            .catch(env)?;
+
+        Ok(self.id.get_or_init(|| (id, sig)))
+    }
+
+    pub(crate) fn call<'a>(
+        &self,
+        args: &[JValue],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<JValueOwned<'a>> {
+        let (id, sig) = self.id(env)?;
+        check_args(sig, args)?;
+        let class = self.class.get(env)?;
+        let args: Vec<_> = args.iter().map(JValue::as_jni).collect();
+
+        // SAFETY: the method ID was resolved on `class` from `sig`, whose return type is
+        // passed along and whose argument types were checked above
+        unsafe { env.call_static_method_unchecked(class, *id, sig.ret.clone(), &args) }.catch(env)
+    }
+}
+
+/// A constructor, resolved once
+pub(crate) struct Constructor {
+    class: &'static Class,
+    sig: &'static str,
+    id: OnceLock<(JMethodID, TypeSignature)>,
+}
+
+impl Constructor {
+    pub(crate) const fn new(class: &'static Class, sig: &'static str) -> Self {
+        Self {
+            class,
+            sig,
+            id: OnceLock::new(),
+        }
+    }
+
+    fn id(&self, env: &mut JNIEnv<'_>) -> Result<&(JMethodID, TypeSignature)> {
+        if let Some(id) = self.id.get() {
+            return Ok(id);
+        }
+
+        let sig = TypeSignature::from_str(self.sig)?;
+        let class = self.class.get(env)?;
+        let id = env.get_method_id(class, "<init>", self.sig).catch(env)?;
+
+        Ok(self.id.get_or_init(|| (id, sig)))
+    }
+
+    pub(crate) fn new_object<'a>(
+        &self,
+        args: &[JValue],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<JObject<'a>> {
+        let (id, sig) = self.id(env)?;
+        check_args(sig, args)?;
+        let class = self.class.get(env)?;
+        let args: Vec<_> = args.iter().map(JValue::as_jni).collect();
+
+        // SAFETY: the constructor ID was resolved on `class` from `sig`,
+        // whose argument types were checked above
+        unsafe { env.new_object_unchecked(class, *id, &args) }.catch(env)
+    }
+}
@@ -0,0 +1,104 @@
+use jni::{
+    JNIEnv,
+    objects::{JByteArray, JObjectArray, JString, JValue},
+};
+
+use crate::{
+    Error, LOCAL_FRAME_CAPACITY, Object, Result,
+    cache::{Class, Constructor, Method, StaticMethod},
+    keypair::PublicKey,
+    sealed::Wrap,
+};
+
+static CERTIFICATE_FACTORY: Class = Class::new("java/security/cert/CertificateFactory");
+static BYTE_ARRAY_INPUT_STREAM: Class = Class::new("java/io/ByteArrayInputStream");
+
+java_class! {
+    /// A wrapper around a JObject representing a `java.security.cert.Certificate`
+    Certificate(pub(crate) CERTIFICATE = "java/security/cert/Certificate"),
+    owned OwnedCertificate
+}
+
+impl<'a> Certificate<'a> {
+    /// Parses an X.509 certificate, DER encoded or PEM armored
+    ///
+    /// Malformed input fails with [`Error::InvalidCertificate`].
+    pub fn from_bytes(bytes: &[u8], env: &mut JNIEnv<'a>) -> Result<Self> {
+        static GET_INSTANCE: StaticMethod = StaticMethod::new(
+            &CERTIFICATE_FACTORY,
+            "getInstance",
+            "(Ljava/lang/String;)Ljava/security/cert/CertificateFactory;",
+        );
+        static GENERATE_CERTIFICATE: Method = Method::new(
+            &CERTIFICATE_FACTORY,
+            "generateCertificate",
+            "(Ljava/io/InputStream;)Ljava/security/cert/Certificate;",
+        );
+        static NEW_BYTE_ARRAY_INPUT_STREAM: Constructor =
+            Constructor::new(&BYTE_ARRAY_INPUT_STREAM, "([B)V");
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                let x509 = env.new_string("X.509")?;
+                let factory = GET_INSTANCE.call(&[JValue::Object(&x509)], env)?.l()?;
+                let bytes = env.byte_array_from_slice(bytes)?;
+                let input =
+                    NEW_BYTE_ARRAY_INPUT_STREAM.new_object(&[JValue::Object(&bytes)], env)?;
+
+                Ok::<_, Error>(
+                    GENERATE_CERTIFICATE
+                        .call(&factory, &[JValue::Object(&input)], env)?
+                        .l()?,
+                )
+            })?
+            .wrap())
+    }
+
+    /// The encoded certificate, DER for X.509 certificates
+    pub fn get_encoded(&self, env: &mut JNIEnv<'a>) -> Result<Vec<u8>> {
+        static GET_ENCODED: Method = Method::new(&CERTIFICATE, "getEncoded", "()[B");
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let bytes: JByteArray = GET_ENCODED.call(self.l(), &[], env)?.l()?.into();
+
+            Ok(env.convert_byte_array(bytes)?)
+        })
+    }
+
+    /// The certificate type, e.g. `X.509`
+    pub fn get_type(&self, env: &mut JNIEnv<'a>) -> Result<String> {
+        static GET_TYPE: Method = Method::new(&CERTIFICATE, "getType", "()Ljava/lang/String;");
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let cert_type: JString = GET_TYPE.call(self.l(), &[], env)?.l()?.into();
+
+            Ok(env.get_string(&cert_type)?.into())
+        })
+    }
+
+    pub fn get_public_key(&self, env: &mut JNIEnv<'a>) -> Result<PublicKey<'a>> {
+        static GET_PUBLIC_KEY: Method =
+            Method::new(&CERTIFICATE, "getPublicKey", "()Ljava/security/PublicKey;");
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(GET_PUBLIC_KEY.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
+    }
+}
+
+/// DER encodings of a `Certificate[]`, in array order
+pub(crate) fn chain_to_der(chain: &JObjectArray<'_>, env: &mut JNIEnv<'_>) -> Result<Vec<Vec<u8>>> {
+    let mut res = vec![];
+    for i in 0..env.get_array_length(chain)? {
+        // One frame per certificate, chains can be long with attestation
+        let der = env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let certificate: Certificate = env.get_object_array_element(chain, i)?.wrap();
+            certificate.get_encoded(env)
+        })?;
+        res.push(der);
+    }
+
+    Ok(res)
+}
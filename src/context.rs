@@ -0,0 +1,15 @@
+use jni::JNIEnv;
+
+use crate::{Result, utils::get_internal_directory_path};
+
+java_class! {
+    /// A wrapper around a JObject representing an `android.content.Context`
+    Context(pub(crate) CONTEXT = "android/content/Context")
+}
+
+impl<'a> Context<'a> {
+    /// Absolute path of the app's private files directory, `getFilesDir()`
+    pub fn get_files_dir(&self, env: &mut JNIEnv<'a>) -> Result<String> {
+        get_internal_directory_path(env, self)
+    }
+}
@@ -0,0 +1,166 @@
+//! Minimal DER encoder, enough for the ASN.1 structures Keymaster/KeyMint parse
+
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const NULL: u8 = 0x05;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+
+fn length(len: usize, out: &mut Vec<u8>) {
+    if len < 0x80 {
+        out.push(len as u8);
+        return;
+    }
+
+    let bytes = len.to_be_bytes();
+    let skip = bytes.iter().take_while(|b| **b == 0).count();
+    out.push(0x80 | (bytes.len() - skip) as u8);
+    out.extend_from_slice(&bytes[skip..]);
+}
+
+fn tlv(tag: &[u8], content: &[u8]) -> Vec<u8> {
+    let mut out = tag.to_vec();
+    length(content.len(), &mut out);
+    out.extend_from_slice(content);
+    out
+}
+
+/// Two's complement, big-endian, on the fewest bytes that keep the sign
+pub(crate) fn integer(value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < bytes.len() - 1 {
+        let redundant = (bytes[start] == 0x00 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xff && bytes[start + 1] & 0x80 != 0);
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+
+    tlv(&[INTEGER], &bytes[start..])
+}
+
+/// Big-endian on the fewest bytes, with a leading zero when the top bit would read as a sign
+pub(crate) fn unsigned_integer(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let skip = bytes[..bytes.len() - 1]
+        .iter()
+        .take_while(|b| **b == 0)
+        .count();
+    let mut content = vec![];
+    if bytes[skip] & 0x80 != 0 {
+        content.push(0x00);
+    }
+    content.extend_from_slice(&bytes[skip..]);
+
+    tlv(&[INTEGER], &content)
+}
+
+pub(crate) fn octet_string(bytes: &[u8]) -> Vec<u8> {
+    tlv(&[OCTET_STRING], bytes)
+}
+
+pub(crate) fn null() -> Vec<u8> {
+    tlv(&[NULL], &[])
+}
+
+pub(crate) fn sequence(elements: &[Vec<u8>]) -> Vec<u8> {
+    tlv(&[SEQUENCE], &elements.concat())
+}
+
+/// `SET OF`, whose elements DER requires in ascending order of their encoding
+pub(crate) fn set_of(mut elements: Vec<Vec<u8>>) -> Vec<u8> {
+    elements.sort();
+    tlv(&[SET], &elements.concat())
+}
+
+/// `[number] EXPLICIT`, context-specific and constructed
+pub(crate) fn explicit(number: u32, content: &[u8]) -> Vec<u8> {
+    if number < 31 {
+        return tlv(&[0xa0 | number as u8], content);
+    }
+
+    // High tag number form: base 128, most significant group first
+    let mut tag = vec![];
+    let mut rest = number;
+    loop {
+        tag.push((rest & 0x7f) as u8 | if tag.is_empty() { 0 } else { 0x80 });
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    tag.push(0xbf);
+    tag.reverse();
+
+    tlv(&tag, content)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn hex(s: &str) -> Vec<u8> {
+        let s: String = s.split_whitespace().collect();
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn integer_is_minimal() {
+        assert_eq!(integer(0), hex("02 01 00"));
+        assert_eq!(integer(127), hex("02 01 7f"));
+        assert_eq!(integer(128), hex("02 02 0080"));
+        assert_eq!(integer(-1), hex("02 01 ff"));
+        assert_eq!(integer(-128), hex("02 01 80"));
+        assert_eq!(integer(-129), hex("02 02 ff7f"));
+        assert_eq!(integer(65537), hex("02 03 010001"));
+    }
+
+    #[test]
+    fn unsigned_integer_keeps_the_sign_bit_clear() {
+        assert_eq!(unsigned_integer(0), hex("02 01 00"));
+        assert_eq!(unsigned_integer(127), hex("02 01 7f"));
+        assert_eq!(unsigned_integer(128), hex("02 02 0080"));
+        assert_eq!(unsigned_integer(65537), hex("02 03 010001"));
+        assert_eq!(unsigned_integer(u64::MAX), hex("02 09 00ffffffffffffffff"));
+    }
+
+    #[test]
+    fn length_switches_to_long_form() {
+        assert_eq!(octet_string(&[0; 0x7f])[..2], hex("04 7f"));
+        assert_eq!(octet_string(&[0; 0x80])[..3], hex("04 81 80"));
+        assert_eq!(octet_string(&[0; 0xff])[..3], hex("04 81 ff"));
+        assert_eq!(octet_string(&[0; 0x100])[..4], hex("04 82 0100"));
+    }
+
+    #[test]
+    fn explicit_tags() {
+        assert_eq!(explicit(1, &null()), hex("a1 02 0500"));
+        assert_eq!(explicit(30, &null()), hex("be 02 0500"));
+        assert_eq!(explicit(31, &null()), hex("bf 1f 02 0500"));
+        assert_eq!(explicit(200, &null()), hex("bf 81 48 02 0500"));
+        assert_eq!(explicit(503, &null()), hex("bf 83 77 02 0500"));
+        assert_eq!(explicit(504, &null()), hex("bf 83 78 02 0500"));
+        assert_eq!(explicit(505, &null()), hex("bf 83 79 02 0500"));
+    }
+
+    #[test]
+    fn set_of_sorts_its_elements() {
+        let set = set_of(vec![integer(3), integer(1), integer(2)]);
+        assert_eq!(set, hex("31 09 020101 020102 020103"));
+
+        // Ordered by encoding, so the longer 128 comes after 3
+        let set = set_of(vec![integer(128), integer(3)]);
+        assert_eq!(set, hex("31 07 020103 02020080"));
+    }
+
+    #[test]
+    fn sequence_keeps_its_order() {
+        let sequence = sequence(&[integer(2), null(), integer(1)]);
+        assert_eq!(sequence, hex("30 08 020102 0500 020101"));
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt::Write;
+
+/// An X.500 distinguished name, e.g. the subject of a certificate
+///
+/// Attributes left to `None` are omitted.
+#[derive(Debug, Clone, Default)]
+pub struct DistinguishedName {
+    pub common_name: Option<String>,
+    pub organizational_unit: Option<String>,
+    pub organization: Option<String>,
+    pub locality: Option<String>,
+    pub state: Option<String>,
+    /// Two-letter ISO 3166 code
+    pub country: Option<String>,
+}
+
+impl DistinguishedName {
+    pub fn with_common_name(common_name: impl Into<String>) -> Self {
+        Self {
+            common_name: Some(common_name.into()),
+            ..Default::default()
+        }
+    }
+
+    /// RFC 2253 string representation, as parsed by `javax.security.auth.x500.X500Principal`
+    ///
+    /// Attributes go from the most specific to the least, `CN=example,O=Example,C=FR`.
+    pub fn to_rfc2253(&self) -> String {
+        let attributes = [
+            ("CN", &self.common_name),
+            ("OU", &self.organizational_unit),
+            ("O", &self.organization),
+            ("L", &self.locality),
+            ("ST", &self.state),
+            ("C", &self.country),
+        ];
+
+        let mut res = String::new();
+        for (key, value) in attributes {
+            let Some(value) = value else { continue };
+            if !res.is_empty() {
+                res.push(',');
+            }
+            let _ = write!(res, "{key}={}", escape(value));
+        }
+        res
+    }
+}
+
+/// Escapes an attribute value as required by RFC 2253, section 2.4
+fn escape(value: &str) -> String {
+    let last = value.chars().count().saturating_sub(1);
+    let mut res = String::with_capacity(value.len());
+    for (i, c) in value.chars().enumerate() {
+        let special = matches!(c, ',' | '+' | '"' | '\\' | '<' | '>' | ';')
+            || (i == 0 && matches!(c, '#' | ' '))
+            || (i == last && c == ' ');
+        if special {
+            res.push('\\');
+        }
+        res.push(c);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_special_characters() {
+        assert_eq!(escape("plain value"), "plain value");
+        assert_eq!(escape("a,b+c"), r"a\,b\+c");
+        assert_eq!(escape(r#"say "hi""#), r#"say \"hi\""#);
+        assert_eq!(escape(r"back\slash"), r"back\\slash");
+        assert_eq!(escape("<a>;b"), r"\<a\>\;b");
+    }
+
+    #[test]
+    fn escape_leading_and_trailing() {
+        assert_eq!(escape("#hash"), r"\#hash");
+        assert_eq!(escape("in#side"), "in#side");
+        assert_eq!(escape(" leading"), r"\ leading");
+        assert_eq!(escape("trailing "), r"trailing\ ");
+        assert_eq!(escape(" both "), r"\ both\ ");
+        assert_eq!(escape(" "), r"\ ");
+        assert_eq!(escape(""), "");
+    }
+
+    #[test]
+    fn attribute_order() {
+        let name = DistinguishedName {
+            country: Some("FR".into()),
+            state: Some("Ile-de-France".into()),
+            locality: Some("Paris".into()),
+            organization: Some("Example".into()),
+            organizational_unit: Some("Keys".into()),
+            common_name: Some("example".into()),
+        };
+        assert_eq!(
+            name.to_rfc2253(),
+            "CN=example,OU=Keys,O=Example,L=Paris,ST=Ile-de-France,C=FR"
+        );
+    }
+
+    #[test]
+    fn missing_attributes_are_omitted() {
+        assert_eq!(DistinguishedName::default().to_rfc2253(), "");
+        assert_eq!(
+            DistinguishedName::with_common_name("example").to_rfc2253(),
+            "CN=example"
+        );
+
+        let name = DistinguishedName {
+            organization: Some("Example, Inc.".into()),
+            country: Some("US".into()),
+            ..Default::default()
+        };
+        assert_eq!(name.to_rfc2253(), r"O=Example\, Inc.,C=US");
+    }
+}
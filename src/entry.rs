@@ -0,0 +1,206 @@
+use jni::{
+    JNIEnv,
+    objects::{JObject, JObjectArray, JValue},
+};
+
+use crate::{
+    Error, LOCAL_FRAME_CAPACITY, Object, Result,
+    cache::{Constructor, Method},
+    certificate::{Certificate, chain_to_der},
+    keypair::PrivateKey,
+    sealed::Wrap,
+    secret_key::SecretKey,
+    utils::class_name,
+};
+
+java_class! {
+    PrivateKeyEntry(PRIVATE_KEY_ENTRY = "java/security/KeyStore$PrivateKeyEntry"),
+    owned OwnedPrivateKeyEntry
+}
+
+java_class! {
+    SecretKeyEntry(SECRET_KEY_ENTRY = "java/security/KeyStore$SecretKeyEntry"),
+    owned OwnedSecretKeyEntry
+}
+
+java_class! {
+    TrustedCertificateEntry(
+        TRUSTED_CERTIFICATE_ENTRY = "java/security/KeyStore$TrustedCertificateEntry"
+    ),
+    owned OwnedTrustedCertificateEntry
+}
+
+/// A `java.security.KeyStore$Entry`, as returned by [`crate::AndroidKeyStore::get_entry`]
+#[derive(Debug)]
+pub enum Entry<'a> {
+    PrivateKey(PrivateKeyEntry<'a>),
+    SecretKey(SecretKeyEntry<'a>),
+    TrustedCertificate(TrustedCertificateEntry<'a>),
+}
+
+impl<'a> Entry<'a> {
+    /// Wraps a non-null `KeyStore$Entry` in the variant matching its class, checked with
+    /// [`Object::is_instance`] like [`crate::Cast::try_cast`]
+    pub fn try_from_object(obj: JObject<'a>, env: &mut JNIEnv<'a>) -> Result<Self> {
+        if PrivateKeyEntry::is_instance(&obj, env)? {
+            return Ok(Entry::PrivateKey(obj.wrap()));
+        }
+        if SecretKeyEntry::is_instance(&obj, env)? {
+            return Ok(Entry::SecretKey(obj.wrap()));
+        }
+        if TrustedCertificateEntry::is_instance(&obj, env)? {
+            return Ok(Entry::TrustedCertificate(obj.wrap()));
+        }
+
+        Err(Error::ClassCast {
+            expected: "java/security/KeyStore$PrivateKeyEntry, SecretKeyEntry or TrustedCertificateEntry",
+            actual: class_name(&obj, env)?,
+        })
+    }
+
+    pub fn l(&self) -> &JObject<'a> {
+        match self {
+            Entry::PrivateKey(entry) => entry.l(),
+            Entry::SecretKey(entry) => entry.l(),
+            Entry::TrustedCertificate(entry) => entry.l(),
+        }
+    }
+
+    pub fn as_private_key_entry(&self) -> Option<&PrivateKeyEntry<'a>> {
+        match self {
+            Entry::PrivateKey(entry) => Some(entry),
+            _ => None,
+        }
+    }
+
+    pub fn as_secret_key_entry(&self) -> Option<&SecretKeyEntry<'a>> {
+        match self {
+            Entry::SecretKey(entry) => Some(entry),
+            _ => None,
+        }
+    }
+
+    pub fn as_trusted_certificate_entry(&self) -> Option<&TrustedCertificateEntry<'a>> {
+        match self {
+            Entry::TrustedCertificate(entry) => Some(entry),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> PrivateKeyEntry<'a> {
+    /// An entry holding `private_key` and its certificate chain, leaf first
+    pub fn new(
+        private_key: &PrivateKey<'a>,
+        chain: &[Certificate<'a>],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static NEW: Constructor = Constructor::new(
+            &PRIVATE_KEY_ENTRY,
+            "(Ljava/security/PrivateKey;[Ljava/security/cert/Certificate;)V",
+        );
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                let certificate_class = Certificate::class(env)?;
+                let array =
+                    env.new_object_array(chain.len() as i32, &certificate_class, JObject::null())?;
+                for (i, certificate) in chain.iter().enumerate() {
+                    env.set_object_array_element(&array, i as i32, certificate.l())?;
+                }
+
+                NEW.new_object(
+                    &[JValue::Object(private_key.l()), JValue::Object(&array)],
+                    env,
+                )
+            })?
+            .wrap())
+    }
+
+    pub fn get_private_key(&self, env: &mut JNIEnv<'a>) -> Result<PrivateKey<'a>> {
+        static GET_PRIVATE_KEY: Method = Method::new(
+            &PRIVATE_KEY_ENTRY,
+            "getPrivateKey",
+            "()Ljava/security/PrivateKey;",
+        );
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(GET_PRIVATE_KEY.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
+    }
+
+    /// DER encodings of the entry's certificate chain, leaf first
+    pub fn get_certificate_chain(&self, env: &mut JNIEnv<'a>) -> Result<Vec<Vec<u8>>> {
+        static GET_CERTIFICATE_CHAIN: Method = Method::new(
+            &PRIVATE_KEY_ENTRY,
+            "getCertificateChain",
+            "()[Ljava/security/cert/Certificate;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let chain: JObjectArray = GET_CERTIFICATE_CHAIN.call(self.l(), &[], env)?.l()?.into();
+
+            chain_to_der(&chain, env)
+        })
+    }
+
+    /// The leaf certificate of the entry's certificate chain
+    pub fn get_certificate(&self, env: &mut JNIEnv<'a>) -> Result<Certificate<'a>> {
+        static GET_CERTIFICATE: Method = Method::new(
+            &PRIVATE_KEY_ENTRY,
+            "getCertificate",
+            "()Ljava/security/cert/Certificate;",
+        );
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(GET_CERTIFICATE.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
+    }
+}
+
+impl<'a> SecretKeyEntry<'a> {
+    pub fn new(secret_key: &SecretKey<'a>, env: &mut JNIEnv<'a>) -> Result<Self> {
+        static NEW: Constructor =
+            Constructor::new(&SECRET_KEY_ENTRY, "(Ljavax/crypto/SecretKey;)V");
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                NEW.new_object(&[JValue::Object(secret_key.l())], env)
+            })?
+            .wrap())
+    }
+
+    pub fn get_secret_key(&self, env: &mut JNIEnv<'a>) -> Result<SecretKey<'a>> {
+        static GET_SECRET_KEY: Method = Method::new(
+            &SECRET_KEY_ENTRY,
+            "getSecretKey",
+            "()Ljavax/crypto/SecretKey;",
+        );
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(GET_SECRET_KEY.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
+    }
+}
+
+impl<'a> TrustedCertificateEntry<'a> {
+    pub fn get_trusted_certificate(&self, env: &mut JNIEnv<'a>) -> Result<Certificate<'a>> {
+        static GET_TRUSTED_CERTIFICATE: Method = Method::new(
+            &TRUSTED_CERTIFICATE_ENTRY,
+            "getTrustedCertificate",
+            "()Ljava/security/cert/Certificate;",
+        );
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(GET_TRUSTED_CERTIFICATE.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
+    }
+}
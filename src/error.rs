@@ -0,0 +1,414 @@
+use std::fmt;
+
+use crate::exception::{JavaException, KEYSTORE_EXCEPTION};
+
+/// Errors returned by every public API of this crate
+#[derive(Debug)]
+pub enum Error {
+    /// A JNI call failed (missing class or method, wrong signature, detached thread, ...)
+    Jni(jni::errors::Error),
+    /// A Java method threw an exception that has no dedicated variant
+    JavaException(JavaException),
+    /// `KeyPermanentlyInvalidatedException`: the key is gone for good (e.g. new biometric enrolled)
+    KeyPermanentlyInvalidated(JavaException),
+    /// `UserNotAuthenticatedException`: the user must authenticate before the key can be used
+    UserNotAuthenticated(JavaException),
+    /// `StrongBoxUnavailableException`: StrongBox was requested but the device has none
+    StrongBoxUnavailable(JavaException),
+    /// `KeyNotYetValidException`, thrown or found in the cause chain: the key validity start
+    /// date is in the future
+    KeyNotYetValid(JavaException),
+    /// `KeyExpiredException`, thrown or found in the cause chain: the key validity end date for
+    /// the attempted operation has passed
+    KeyExpired(JavaException),
+    /// `BadPaddingException` or `AEADBadTagException`: the input is corrupted or was tampered with
+    BadPadding(JavaException),
+    /// `CertificateException`: a certificate could not be parsed
+    InvalidCertificate(JavaException),
+    /// `android.security.KeyStoreException`, thrown or found in the cause chain, with the
+    /// structured details available on API 33+
+    KeyStore {
+        /// The outermost exception, the `KeyStoreException` is in its cause chain
+        exception: JavaException,
+        details: Option<KeyStoreErrorDetails>,
+    },
+    /// Data handed to or returned by Java could not be decoded
+    Decode(String),
+    /// The operation needs a newer Android platform than the one running
+    Unsupported {
+        /// Minimum API level of the operation
+        required: u32,
+        /// API level of the running platform
+        actual: u32,
+    },
+    /// A Java object is not an instance of the class expected by its wrapper
+    ClassCast {
+        /// JNI path of the expected class, or classes
+        expected: &'static str,
+        /// Name of the actual class, or `"null"`
+        actual: String,
+    },
+    /// The key algorithm is not supported by the operation, e.g. importing a DSA key
+    UnsupportedAlgorithm(String),
+    /// A certificate chain is empty or does not match the key it is stored with
+    InvalidCertificateChain(String),
+    /// An alias or namespace of a [`crate::NamespacedKeyStore`] is empty or contains
+    /// [`crate::namespaced::SEPARATOR`]
+    InvalidAlias(String),
+    /// An insecure setting, such as ECB, was requested through the setter that rejects it,
+    /// instead of the one that opts in to it
+    InsecureParameter(&'static str),
+    /// A parameter does not fit the Java type it is passed as, e.g. an `int` key size
+    OutOfRange(&'static str),
+    /// Neither [`crate::init`] nor `ndk_context` provided a JVM
+    NotInitialized,
+    /// [`crate::init`] was called more than once
+    AlreadyInitialized,
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Values of `android.security.KeyStoreException.getRetryPolicy()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryPolicy {
+    Never,
+    WithExponentialBackoff,
+    WhenConnectivityAvailable,
+    AfterNextReboot,
+    Other(i32),
+}
+
+impl From<i32> for RetryPolicy {
+    fn from(value: i32) -> Self {
+        match value {
+            1 => RetryPolicy::Never,
+            2 => RetryPolicy::WithExponentialBackoff,
+            3 => RetryPolicy::WhenConnectivityAvailable,
+            4 => RetryPolicy::AfterNextReboot,
+            other => RetryPolicy::Other(other),
+        }
+    }
+}
+
+/// Details exposed by `android.security.KeyStoreException` since API 33
+#[derive(Debug, Clone, Copy)]
+pub struct KeyStoreErrorDetails {
+    /// `getNumericErrorCode()`, one of the `KeyStoreException.ERROR_*` constants
+    pub code: i32,
+    pub transient_failure: bool,
+    pub system_error: bool,
+    pub requires_user_authentication: bool,
+    /// `getRetryPolicy()`, API 34+
+    pub retry_policy: Option<RetryPolicy>,
+}
+
+impl KeyStoreErrorDetails {
+    /// `KeyStoreException.ERROR_KEY_CORRUPTED`
+    pub const ERROR_KEY_CORRUPTED: i32 = 7;
+}
+
+/// What the caller can do to recover from an [`Error`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recovery {
+    /// Authenticate the user (biometrics or device credential), then try again
+    AuthenticateUser,
+    /// The key cannot be used anymore, delete and regenerate it
+    RegenerateKey,
+    /// The key is past its validity end for this operation, use a new key for new data. Keep
+    /// this one, it may still decrypt or verify until its consumption end date
+    UseNewKeyForOrigination,
+    /// The failure is transient, try again later
+    RetryLater,
+    /// Retrying will not help
+    None,
+}
+
+impl Error {
+    /// Maps a Java exception to its dedicated variant, if any
+    pub fn from_java_exception(
+        exception: JavaException,
+        details: Option<KeyStoreErrorDetails>,
+    ) -> Self {
+        match exception.class.as_str() {
+            "android.security.keystore.KeyPermanentlyInvalidatedException" => {
+                Error::KeyPermanentlyInvalidated(exception)
+            }
+            "android.security.keystore.UserNotAuthenticatedException" => {
+                Error::UserNotAuthenticated(exception)
+            }
+            "android.security.keystore.StrongBoxUnavailableException" => {
+                Error::StrongBoxUnavailable(exception)
+            }
+            "android.security.keystore.KeyNotYetValidException" => Error::KeyNotYetValid(exception),
+            "android.security.keystore.KeyExpiredException" => Error::KeyExpired(exception),
+            "javax.crypto.BadPaddingException" | "javax.crypto.AEADBadTagException" => {
+                Error::BadPadding(exception)
+            }
+            "java.security.cert.CertificateException"
+            | "java.security.cert.CertificateParsingException" => {
+                Error::InvalidCertificate(exception)
+            }
+            KEYSTORE_EXCEPTION => Error::KeyStore { exception, details },
+            _ => Self::from_cause(exception, details),
+        }
+    }
+
+    /// Validity and keystore errors are usually wrapped, e.g. in an `InvalidKeyException` or a
+    /// `ProviderException`, look for them in the cause chain and keep the outermost exception
+    /// for context
+    fn from_cause(exception: JavaException, details: Option<KeyStoreErrorDetails>) -> Self {
+        enum Cause {
+            NotYetValid,
+            Expired,
+            KeyStore,
+        }
+
+        let cause = exception
+            .chain()
+            .skip(1)
+            .find_map(|cause| match cause.class.as_str() {
+                "android.security.keystore.KeyNotYetValidException" => Some(Cause::NotYetValid),
+                "android.security.keystore.KeyExpiredException" => Some(Cause::Expired),
+                KEYSTORE_EXCEPTION => Some(Cause::KeyStore),
+                _ => None,
+            });
+
+        match cause {
+            Some(Cause::NotYetValid) => Error::KeyNotYetValid(exception),
+            Some(Cause::Expired) => Error::KeyExpired(exception),
+            Some(Cause::KeyStore) => Error::KeyStore { exception, details },
+            None => Error::JavaException(exception),
+        }
+    }
+
+    /// The Java exception behind this error, if any
+    pub fn java_exception(&self) -> Option<&JavaException> {
+        match self {
+            Error::JavaException(exception)
+            | Error::KeyPermanentlyInvalidated(exception)
+            | Error::UserNotAuthenticated(exception)
+            | Error::StrongBoxUnavailable(exception)
+            | Error::KeyNotYetValid(exception)
+            | Error::KeyExpired(exception)
+            | Error::BadPadding(exception)
+            | Error::InvalidCertificate(exception)
+            | Error::KeyStore { exception, .. } => Some(exception),
+            _ => None,
+        }
+    }
+
+    pub fn recovery(&self) -> Recovery {
+        match self {
+            Error::UserNotAuthenticated(_) => Recovery::AuthenticateUser,
+            Error::KeyPermanentlyInvalidated(_) => Recovery::RegenerateKey,
+            Error::KeyExpired(_) => Recovery::UseNewKeyForOrigination,
+            Error::KeyNotYetValid(_) => Recovery::RetryLater,
+            Error::KeyStore {
+                details: Some(details),
+                ..
+            } => {
+                if details.requires_user_authentication {
+                    Recovery::AuthenticateUser
+                } else if details.code == KeyStoreErrorDetails::ERROR_KEY_CORRUPTED {
+                    Recovery::RegenerateKey
+                } else if details.transient_failure
+                    && details.retry_policy != Some(RetryPolicy::Never)
+                {
+                    Recovery::RetryLater
+                } else {
+                    Recovery::None
+                }
+            }
+            _ => Recovery::None,
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.recovery() == Recovery::RetryLater
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Jni(err) => write!(f, "JNI error: {err}"),
+            Error::JavaException(exception) => write!(f, "Java exception thrown: {exception}"),
+            Error::KeyPermanentlyInvalidated(exception) => {
+                write!(f, "Key permanently invalidated: {exception}")
+            }
+            Error::UserNotAuthenticated(exception) => {
+                write!(f, "User not authenticated: {exception}")
+            }
+            Error::StrongBoxUnavailable(exception) => {
+                write!(f, "StrongBox unavailable: {exception}")
+            }
+            Error::KeyNotYetValid(exception) => write!(f, "Key not yet valid: {exception}"),
+            Error::KeyExpired(exception) => write!(f, "Key expired: {exception}"),
+            Error::BadPadding(exception) => write!(f, "Bad padding: {exception}"),
+            Error::InvalidCertificate(exception) => write!(f, "Invalid certificate: {exception}"),
+            Error::KeyStore {
+                exception,
+                details: Some(details),
+            } => write!(f, "KeyStore error {}: {exception}", details.code),
+            Error::KeyStore { exception, .. } => write!(f, "KeyStore error: {exception}"),
+            Error::Decode(msg) => write!(f, "Decoding error: {msg}"),
+            Error::Unsupported { required, actual } => write!(
+                f,
+                "Unsupported operation: requires API level {required}, running on {actual}"
+            ),
+            Error::ClassCast { expected, actual } => write!(
+                f,
+                "Expected an instance of {}, got {actual}",
+                expected.replace('/', ".")
+            ),
+            Error::UnsupportedAlgorithm(algorithm) => {
+                write!(f, "Unsupported key algorithm: {algorithm}")
+            }
+            Error::InvalidCertificateChain(msg) => write!(f, "Invalid certificate chain: {msg}"),
+            Error::InvalidAlias(alias) => write!(f, "Invalid alias: {alias:?}"),
+            Error::InsecureParameter(parameter) => {
+                write!(f, "Insecure parameter without explicit opt-in: {parameter}")
+            }
+            Error::OutOfRange(parameter) => {
+                write!(f, "Parameter out of range of its Java type: {parameter}")
+            }
+            Error::NotInitialized => write!(f, "No JVM available, call init() first"),
+            Error::AlreadyInitialized => write!(f, "init() was already called"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Jni(err) => Some(err),
+            _ => self
+                .java_exception()
+                .map(|exception| exception as &(dyn std::error::Error + 'static)),
+        }
+    }
+}
+
+impl From<jni::errors::Error> for Error {
+    fn from(err: jni::errors::Error) -> Self {
+        Error::Jni(err)
+    }
+}
+
+impl From<base64::DecodeError> for Error {
+    fn from(err: base64::DecodeError) -> Self {
+        Error::Decode(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exception(class: &str, cause: Option<JavaException>) -> JavaException {
+        JavaException {
+            class: class.into(),
+            message: None,
+            stack_trace: vec![],
+            cause: cause.map(Box::new),
+        }
+    }
+
+    fn details(transient_failure: bool, retry_policy: Option<RetryPolicy>) -> KeyStoreErrorDetails {
+        KeyStoreErrorDetails {
+            code: 4,
+            transient_failure,
+            system_error: false,
+            requires_user_authentication: false,
+            retry_policy,
+        }
+    }
+
+    #[test]
+    fn expired_cause() {
+        let expired = exception("android.security.keystore.KeyExpiredException", None);
+        let error = Error::from_java_exception(
+            exception("java.security.InvalidKeyException", Some(expired)),
+            None,
+        );
+
+        // The outer exception is kept for context
+        assert!(matches!(
+            &error,
+            Error::KeyExpired(e) if e.class == "java.security.InvalidKeyException"
+        ));
+        // Not deleted, it may still decrypt data encrypted before the origination end
+        assert_eq!(error.recovery(), Recovery::UseNewKeyForOrigination);
+    }
+
+    #[test]
+    fn not_yet_valid_cause() {
+        let not_yet_valid = exception("android.security.keystore.KeyNotYetValidException", None);
+        let wrapped = exception("java.security.ProviderException", Some(not_yet_valid));
+        let error = Error::from_java_exception(
+            exception("java.security.InvalidKeyException", Some(wrapped)),
+            None,
+        );
+
+        assert!(matches!(error, Error::KeyNotYetValid(_)));
+        assert_eq!(error.recovery(), Recovery::RetryLater);
+        assert!(error.is_transient());
+    }
+
+    #[test]
+    fn unrelated_cause() {
+        let cause = exception("java.lang.IllegalStateException", None);
+        let error = Error::from_java_exception(
+            exception("java.security.InvalidKeyException", Some(cause)),
+            None,
+        );
+
+        assert!(matches!(error, Error::JavaException(_)));
+        assert_eq!(error.recovery(), Recovery::None);
+    }
+
+    #[test]
+    fn keystore_cause() {
+        let keystore = exception(KEYSTORE_EXCEPTION, None);
+        let error = Error::from_java_exception(
+            exception("java.security.ProviderException", Some(keystore)),
+            Some(details(true, Some(RetryPolicy::WithExponentialBackoff))),
+        );
+
+        assert!(matches!(
+            &error,
+            Error::KeyStore { exception, details: Some(_) }
+                if exception.class == "java.security.ProviderException"
+        ));
+        assert_eq!(error.recovery(), Recovery::RetryLater);
+    }
+
+    #[test]
+    fn keystore_retry_policy() {
+        let recovery = |details| {
+            Error::from_java_exception(exception(KEYSTORE_EXCEPTION, None), Some(details))
+                .recovery()
+        };
+
+        assert_eq!(recovery(details(true, None)), Recovery::RetryLater);
+        assert_eq!(
+            recovery(details(true, Some(RetryPolicy::AfterNextReboot))),
+            Recovery::RetryLater
+        );
+        assert_eq!(
+            recovery(details(true, Some(RetryPolicy::Never))),
+            Recovery::None
+        );
+        assert_eq!(recovery(details(false, None)), Recovery::None);
+    }
+
+    #[test]
+    fn retry_policy_from_code() {
+        assert_eq!(RetryPolicy::from(1), RetryPolicy::Never);
+        assert_eq!(RetryPolicy::from(2), RetryPolicy::WithExponentialBackoff);
+        assert_eq!(RetryPolicy::from(3), RetryPolicy::WhenConnectivityAvailable);
+        assert_eq!(RetryPolicy::from(4), RetryPolicy::AfterNextReboot);
+        assert_eq!(RetryPolicy::from(0), RetryPolicy::Other(0));
+        assert_eq!(RetryPolicy::from(5), RetryPolicy::Other(5));
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+
+use jni::{
+    JNIEnv,
+    objects::{JObject, JObjectArray, JString, JThrowable},
+};
+
+use crate::{
+    Error, Result,
+    api_level::{self, api_level},
+    error::KeyStoreErrorDetails,
+};
+
+/// Maximum number of causes followed when walking a `Throwable` cause chain
+const MAX_CAUSE_DEPTH: usize = 16;
+
+pub(crate) const KEYSTORE_EXCEPTION: &str = "android.security.KeyStoreException";
+
+/// A Java `Throwable` pulled out of the JVM, with its class name, message, stack trace and causes
+#[derive(Debug, Clone)]
+pub struct JavaException {
+    /// Fully qualified class name, e.g. `java.security.InvalidAlgorithmParameterException`
+    pub class: String,
+    pub message: Option<String>,
+    /// `StackTraceElement.toString()` of every frame, innermost first
+    pub stack_trace: Vec<String>,
+    pub cause: Option<Box<JavaException>>,
+}
+
+impl JavaException {
+    /// Takes the pending exception out of the JVM and clears it
+    ///
+    /// Returns `None` if no exception is pending
+    pub fn take(env: &mut JNIEnv<'_>) -> Result<Option<Self>> {
+        let throwable = env.exception_occurred()?;
+        if throwable.is_null() {
+            return Ok(None);
+        }
+        env.exception_clear()?;
+
+        let exception = Self::from_throwable(&throwable, env);
+        env.delete_local_ref(throwable)?;
+
+        exception.map(Some)
+    }
+
+    /// Converts a `Throwable` to a Rust value, without touching the pending exception state
+    pub fn from_throwable(throwable: &JThrowable<'_>, env: &mut JNIEnv<'_>) -> Result<Self> {
+        let res = Self::describe(throwable, 0, env);
+        if res.is_err() {
+            // Introspection itself threw, drop it so that the JNI env stays usable
+            env.exception_clear()?;
+        }
+        res
+    }
+
+    fn describe(throwable: &JObject<'_>, depth: usize, env: &mut JNIEnv<'_>) -> Result<Self> {
+        env.with_local_frame(16, |env| {
+            let class = env.get_object_class(throwable)?;
+            let class_name: JString = env
+                .call_method(&class, "getName", "()Ljava/lang/String;", &[])?
+                .l()?
+                .into();
+            let class = env.get_string(&class_name)?.into();
+
+            let message: JString = env
+                .call_method(throwable, "getMessage", "()Ljava/lang/String;", &[])?
+                .l()?
+                .into();
+            let message = match message.is_null() {
+                true => None,
+                false => Some(env.get_string(&message)?.into()),
+            };
+
+            let frames: JObjectArray = env
+                .call_method(
+                    throwable,
+                    "getStackTrace",
+                    "()[Ljava/lang/StackTraceElement;",
+                    &[],
+                )?
+                .l()?
+                .into();
+            let mut stack_trace = vec![];
+            for i in 0..env.get_array_length(&frames)? {
+                let frame = env.get_object_array_element(&frames, i)?;
+                let frame_string: JString = env
+                    .call_method(&frame, "toString", "()Ljava/lang/String;", &[])?
+                    .l()?
+                    .into();
+                stack_trace.push(env.get_string(&frame_string)?.into());
+                env.delete_local_ref(frame_string)?;
+                env.delete_local_ref(frame)?;
+            }
+
+            let cause = env
+                .call_method(throwable, "getCause", "()Ljava/lang/Throwable;", &[])?
+                .l()?;
+            let cause = match cause.is_null() || depth + 1 >= MAX_CAUSE_DEPTH {
+                true => None,
+                false => Some(Box::new(Self::describe(&cause, depth + 1, env)?)),
+            };
+
+            Ok(JavaException {
+                class,
+                message,
+                stack_trace,
+                cause,
+            })
+        })
+    }
+
+    /// Iterates over this exception and all of its causes, outermost first
+    pub fn chain(&self) -> impl Iterator<Item = &JavaException> {
+        std::iter::successors(Some(self), |e| e.cause.as_deref())
+    }
+}
+
+impl fmt::Display for JavaException {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{}: {message}", self.class),
+            None => write!(f, "{}", self.class),
+        }
+    }
+}
+
+impl std::error::Error for JavaException {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.cause
+            .as_deref()
+            .map(|cause| cause as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// Reads the details of an `android.security.KeyStoreException`
+///
+/// Returns `None` below API 33, where these methods do not exist. The retry policy needs API 34.
+fn keystore_error_details(
+    throwable: &JObject<'_>,
+    env: &mut JNIEnv<'_>,
+) -> Result<Option<KeyStoreErrorDetails>> {
+    let api_level = api_level(env)?;
+    if api_level < api_level::TIRAMISU {
+        return Ok(None);
+    }
+
+    let retry_policy = match api_level >= api_level::UPSIDE_DOWN_CAKE {
+        true => Some(
+            env.call_method(throwable, "getRetryPolicy", "()I", &[])?
+                .i()?
+                .into(),
+        ),
+        false => None,
+    };
+
+    Ok(Some(KeyStoreErrorDetails {
+        code: env
+            .call_method(throwable, "getNumericErrorCode", "()I", &[])?
+            .i()?,
+        transient_failure: env
+            .call_method(throwable, "isTransientFailure", "()Z", &[])?
+            .z()?,
+        system_error: env
+            .call_method(throwable, "isSystemError", "()Z", &[])?
+            .z()?,
+        requires_user_authentication: env
+            .call_method(throwable, "requiresUserAuthentication", "()Z", &[])?
+            .z()?,
+        retry_policy,
+    }))
+}
+
+/// Details of the first `android.security.KeyStoreException` in the cause chain of `throwable`,
+/// which is `exception`
+///
+/// The exception usually reaches callers wrapped, e.g. in a `ProviderException` or a
+/// `java.security.KeyStoreException`.
+fn chain_keystore_error_details(
+    throwable: &JThrowable<'_>,
+    exception: &JavaException,
+    env: &mut JNIEnv<'_>,
+) -> Result<Option<KeyStoreErrorDetails>> {
+    let Some(depth) = exception
+        .chain()
+        .position(|e| e.class == KEYSTORE_EXCEPTION)
+    else {
+        return Ok(None);
+    };
+
+    env.with_local_frame(16, |env| {
+        let mut cause = env.new_local_ref(throwable)?;
+        for _ in 0..depth {
+            cause = env
+                .call_method(&cause, "getCause", "()Ljava/lang/Throwable;", &[])?
+                .l()?;
+        }
+
+        keystore_error_details(&cause, env)
+    })
+}
+
+/// Turns `jni::errors::Error::JavaException` into a typed [`Error`]
+/// by pulling the pending `Throwable` out of the JVM
+pub(crate) trait Catch<T> {
+    fn catch(self, env: &mut JNIEnv<'_>) -> Result<T>;
+}
+
+impl<T> Catch<T> for jni::errors::Result<T> {
+    fn catch(self, env: &mut JNIEnv<'_>) -> Result<T> {
+        match self {
+            Err(jni::errors::Error::JavaException) => Err(take_error(env)?),
+            res => Ok(res?),
+        }
+    }
+}
+
+fn take_error(env: &mut JNIEnv<'_>) -> Result<Error> {
+    let throwable = env.exception_occurred()?;
+    if throwable.is_null() {
+        return Ok(Error::Jni(jni::errors::Error::JavaException));
+    }
+    env.exception_clear()?;
+
+    let exception = JavaException::from_throwable(&throwable, env)?;
+    let details = chain_keystore_error_details(&throwable, &exception, env);
+    if details.is_err() {
+        // Introspection itself threw, drop it so that the JNI env stays usable
+        env.exception_clear()?;
+    }
+    env.delete_local_ref(throwable)?;
+    let details = details?;
+
+    Ok(Error::from_java_exception(exception, details))
+}
@@ -0,0 +1,73 @@
+use jni::objects::JValue;
+
+use crate::{
+    Error, JNIEnv, LOCAL_FRAME_CAPACITY, Object, Result,
+    cache::{Method, StaticMethod},
+    keygen_parameter_spec::KeyGenParameterSpec,
+    keypair_generator::Provider,
+    sealed::Wrap,
+    secret_key::{SecretKey, SecretKeyAlgorithm},
+};
+
+java_class! {
+    /// A wrapper around a JObject representing a KeyGenerator instance, the symmetric
+    /// counterpart of [`crate::keypair_generator::KeyPairGenerator`]
+    KeyGenerator(KEY_GENERATOR = "javax/crypto/KeyGenerator"), owned OwnedKeyGenerator
+}
+
+impl<'a> KeyGenerator<'a> {
+    pub fn get_instance(
+        algorithm: SecretKeyAlgorithm,
+        provider: Provider,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static GET_INSTANCE: StaticMethod = StaticMethod::new(
+            &KEY_GENERATOR,
+            "getInstance",
+            "(Ljava/lang/String;Ljava/lang/String;)Ljavax/crypto/KeyGenerator;",
+        );
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                let algorithm = &env.new_string(&algorithm)?;
+
+                let provider = &env.new_string(&provider)?;
+
+                Ok::<_, Error>(
+                    GET_INSTANCE
+                        .call(&[JValue::Object(algorithm), JValue::Object(provider)], env)?
+                        .l()?,
+                )
+            })?
+            .wrap())
+    }
+
+    pub fn init(
+        &self,
+        keygen_parameter_spec: KeyGenParameterSpec<'a>,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()> {
+        static INIT: Method = Method::new(
+            &KEY_GENERATOR,
+            "init",
+            "(Ljava/security/spec/AlgorithmParameterSpec;)V",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            INIT.call(self.l(), &[JValue::Object(keygen_parameter_spec.l())], env)?;
+
+            Ok(())
+        })
+    }
+
+    pub fn generate_key(&self, env: &mut JNIEnv<'a>) -> Result<SecretKey<'a>> {
+        static GENERATE_KEY: Method =
+            Method::new(&KEY_GENERATOR, "generateKey", "()Ljavax/crypto/SecretKey;");
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(GENERATE_KEY.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
+    }
+}
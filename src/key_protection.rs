@@ -0,0 +1,263 @@
+use std::time::SystemTime;
+
+use jni::JNIEnv;
+
+use crate::{
+    Error, JValue, LOCAL_FRAME_CAPACITY, Object, Result, api_level,
+    cache::{Constructor, Method},
+    keygen_parameter_spec::{
+        AuthType, BlockMode, Digest, Padding, Purpose, SignaturePadding, check_block_modes,
+        check_randomized_encryption_required,
+    },
+    sealed::Wrap,
+    utils::{call_with_date, call_with_strings},
+};
+
+java_class! {
+    /// Constraints on the use of a key imported into the Android KeyStore,
+    /// the import counterpart of [`crate::keygen_parameter_spec::KeyGenParameterSpec`]
+    KeyProtection(pub(crate) KEY_PROTECTION = "android/security/keystore/KeyProtection")
+}
+
+java_class! {
+    Builder(BUILDER = "android/security/keystore/KeyProtection$Builder")
+}
+
+impl<'a> Builder<'a> {
+    pub fn new(purposes: &[Purpose], env: &mut JNIEnv<'a>) -> Result<Self> {
+        static NEW: Constructor = Constructor::new(&BUILDER, "(I)V");
+
+        api_level::require(api_level::M, env)?;
+
+        let purposes: i32 = purposes.iter().fold(0, |acc, p| acc | *p as i32);
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                NEW.new_object(&[JValue::Int(purposes)], env)
+            })?
+            .wrap())
+    }
+
+    /// See [`crate::keygen_parameter_spec::Builder::set_block_modes`]
+    pub fn set_block_modes(self, block_modes: &[BlockMode], env: &mut JNIEnv<'a>) -> Result<Self> {
+        check_block_modes(block_modes)?;
+
+        self.set_block_modes_allowing_ecb(block_modes, env)
+    }
+
+    /// [`Self::set_block_modes`], accepting ECB
+    pub fn set_block_modes_allowing_ecb(
+        self,
+        block_modes: &[BlockMode],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_BLOCK_MODES: Method = Method::new(
+            &BUILDER,
+            "setBlockModes",
+            "([Ljava/lang/String;)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        call_with_strings(self.l(), &SET_BLOCK_MODES, block_modes, env)?;
+
+        Ok(self)
+    }
+
+    /// See [`crate::keygen_parameter_spec::Builder::set_randomized_encryption_required`]
+    pub fn set_randomized_encryption_required(
+        self,
+        required: bool,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        check_randomized_encryption_required(required)?;
+
+        self.call_set_randomized_encryption_required(required, env)
+    }
+
+    /// See [`crate::keygen_parameter_spec::Builder::disable_randomized_encryption`]
+    pub fn disable_randomized_encryption(self, env: &mut JNIEnv<'a>) -> Result<Self> {
+        self.call_set_randomized_encryption_required(false, env)
+    }
+
+    fn call_set_randomized_encryption_required(
+        self,
+        required: bool,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_RANDOMIZED_ENCRYPTION_REQUIRED: Method = Method::new(
+            &BUILDER,
+            "setRandomizedEncryptionRequired",
+            "(Z)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            SET_RANDOMIZED_ENCRYPTION_REQUIRED.call(
+                self.l(),
+                &[JValue::Bool(required as u8)],
+                env,
+            )?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    pub fn set_digests(self, digests: &[Digest], env: &mut JNIEnv<'a>) -> Result<Self> {
+        static SET_DIGESTS: Method = Method::new(
+            &BUILDER,
+            "setDigests",
+            "([Ljava/lang/String;)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        call_with_strings(self.l(), &SET_DIGESTS, digests, env)?;
+
+        Ok(self)
+    }
+
+    pub fn set_encryption_paddings(
+        self,
+        paddings: &[Padding],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_ENCRYPTION_PADDINGS: Method = Method::new(
+            &BUILDER,
+            "setEncryptionPaddings",
+            "([Ljava/lang/String;)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        call_with_strings(self.l(), &SET_ENCRYPTION_PADDINGS, paddings, env)?;
+
+        Ok(self)
+    }
+
+    pub fn set_signature_paddings(
+        self,
+        paddings: &[SignaturePadding],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_SIGNATURE_PADDINGS: Method = Method::new(
+            &BUILDER,
+            "setSignaturePaddings",
+            "([Ljava/lang/String;)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        call_with_strings(self.l(), &SET_SIGNATURE_PADDINGS, paddings, env)?;
+
+        Ok(self)
+    }
+
+    pub fn set_user_authentication_parameters(
+        self,
+        timeout: usize,
+        auth_type: &[AuthType],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_USER_AUTHENTICATION_PARAMETERS: Method = Method::new(
+            &BUILDER,
+            "setUserAuthenticationParameters",
+            "(II)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        api_level::require(api_level::R, env)?;
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            SET_USER_AUTHENTICATION_PARAMETERS.call(
+                self.l(),
+                &[
+                    JValue::Int(timeout as i32),
+                    JValue::Int(auth_type.iter().fold(0, |acc, p| acc | *p as i32)),
+                ],
+                env,
+            )?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    pub fn set_user_authentication_required(
+        self,
+        required: bool,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_USER_AUTHENTICATION_REQUIRED: Method = Method::new(
+            &BUILDER,
+            "setUserAuthenticationRequired",
+            "(Z)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            SET_USER_AUTHENTICATION_REQUIRED.call(
+                self.l(),
+                &[JValue::Bool(required as u8)],
+                env,
+            )?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    pub fn build(self, env: &mut JNIEnv<'a>) -> Result<KeyProtection<'a>> {
+        static BUILD: Method = Method::new(
+            &BUILDER,
+            "build",
+            "()Landroid/security/keystore/KeyProtection;",
+        );
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(BUILD.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
+    }
+
+    /// See [`crate::keygen_parameter_spec::Builder::set_key_validity_start`]
+    pub fn set_key_validity_start(self, start: SystemTime, env: &mut JNIEnv<'a>) -> Result<Self> {
+        static SET_KEY_VALIDITY_START: Method = Method::new(
+            &BUILDER,
+            "setKeyValidityStart",
+            "(Ljava/util/Date;)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        call_with_date(self.l(), &SET_KEY_VALIDITY_START, start, env)?;
+
+        Ok(self)
+    }
+
+    /// See [`crate::keygen_parameter_spec::Builder::set_key_validity_for_origination_end`]
+    pub fn set_key_validity_for_origination_end(
+        self,
+        end: SystemTime,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_KEY_VALIDITY_FOR_ORIGINATION_END: Method = Method::new(
+            &BUILDER,
+            "setKeyValidityForOriginationEnd",
+            "(Ljava/util/Date;)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        call_with_date(self.l(), &SET_KEY_VALIDITY_FOR_ORIGINATION_END, end, env)?;
+
+        Ok(self)
+    }
+
+    /// See [`crate::keygen_parameter_spec::Builder::set_key_validity_for_consumption_end`]
+    pub fn set_key_validity_for_consumption_end(
+        self,
+        end: SystemTime,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_KEY_VALIDITY_FOR_CONSUMPTION_END: Method = Method::new(
+            &BUILDER,
+            "setKeyValidityForConsumptionEnd",
+            "(Ljava/util/Date;)Landroid/security/keystore/KeyProtection$Builder;",
+        );
+
+        call_with_date(self.l(), &SET_KEY_VALIDITY_FOR_CONSUMPTION_END, end, env)?;
+
+        Ok(self)
+    }
+}
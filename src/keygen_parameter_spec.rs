@@ -1,6 +1,20 @@
-use jni::{AttachGuard, objects::JClass};
+use std::time::SystemTime;
 
-use crate::{JNIString, JObject, JValue, Object, utils::make_string_array};
+use jni::{JNIEnv, objects::JObject};
+
+use crate::{
+    Error, JNIString, JValue, LOCAL_FRAME_CAPACITY, Object, Result, api_level,
+    cache::{Class, Constructor, Method},
+    distinguished_name::DistinguishedName,
+    sealed::Wrap,
+    utils::{call_with_date, call_with_strings},
+};
+
+static RSA_KEY_GEN_PARAMETER_SPEC: Class = Class::new("java/security/spec/RSAKeyGenParameterSpec");
+static EC_GEN_PARAMETER_SPEC: Class = Class::new("java/security/spec/ECGenParameterSpec");
+static NAMED_PARAMETER_SPEC: Class = Class::new("java/security/spec/NamedParameterSpec");
+static BIG_INTEGER: Class = Class::new("java/math/BigInteger");
+static X500_PRINCIPAL: Class = Class::new("javax/security/auth/x500/X500Principal");
 
 #[repr(i32)]
 #[derive(Debug, Clone, Copy)]
@@ -56,155 +70,566 @@ impl From<&Padding> for JNIString {
     }
 }
 
-#[repr(i32)]
-#[derive(Debug, Clone, Copy)]
-pub enum AuthType {
-    DeviceCredential = 1,
-    BiometricStrong = 2,
+/// Paddings of RSA signatures, distinct from the encryption [`Padding`]s
+pub enum SignaturePadding {
+    /// PKCS#1 v1.5
+    Pkcs1,
+    Pss,
 }
 
-#[derive(Debug)]
-pub struct KeyGenParameterSpec<'a>(JObject<'a>);
+impl From<&SignaturePadding> for JNIString {
+    fn from(val: &SignaturePadding) -> Self {
+        match val {
+            SignaturePadding::Pkcs1 => "PKCS1".into(),
+            SignaturePadding::Pss => "PSS".into(),
+        }
+    }
+}
+
+pub enum BlockMode {
+    Ecb,
+    Cbc,
+    Ctr,
+    Gcm,
+}
 
-impl<'a> From<JObject<'a>> for KeyGenParameterSpec<'a> {
-    fn from(value: JObject<'a>) -> Self {
-        Self(value)
+impl From<&BlockMode> for JNIString {
+    fn from(val: &BlockMode) -> Self {
+        match val {
+            BlockMode::Ecb => "ECB".into(),
+            BlockMode::Cbc => "CBC".into(),
+            BlockMode::Ctr => "CTR".into(),
+            BlockMode::Gcm => "GCM".into(),
+        }
     }
 }
 
-impl<'a> Object<'a> for KeyGenParameterSpec<'a> {
-    fn class(env: &mut AttachGuard<'a>) -> JClass<'a> {
-        env.find_class("android/security/keystore/KeyGenParameterSpec")
-            .expect("Failed to find KeyGenParameterSpec class")
+/// ECB leaks patterns of the plaintext, so the plain block mode setters of both builders reject
+/// it with [`Error::InsecureParameter`]
+pub(crate) fn check_block_modes(block_modes: &[BlockMode]) -> Result<()> {
+    match block_modes
+        .iter()
+        .any(|mode| matches!(mode, BlockMode::Ecb))
+    {
+        true => Err(Error::InsecureParameter("ECB block mode")),
+        false => Ok(()),
     }
+}
 
-    fn l(&self) -> &JObject<'a> {
-        &self.0
+/// Randomized encryption is required by default, the builders only turn it off through
+/// `disable_randomized_encryption`
+pub(crate) fn check_randomized_encryption_required(required: bool) -> Result<()> {
+    match required {
+        true => Ok(()),
+        false => Err(Error::InsecureParameter(
+            "randomized encryption not required",
+        )),
     }
 }
 
-#[derive(Debug)]
-pub struct Builder<'a>(JObject<'a>);
+/// NIST curves supported by the Android KeyStore
+#[derive(Debug, Clone, Copy)]
+pub enum EcCurve {
+    P256,
+    P384,
+    P521,
+}
 
-impl<'a> From<JObject<'a>> for Builder<'a> {
-    fn from(value: JObject<'a>) -> Self {
-        Self(value)
+impl From<&EcCurve> for JNIString {
+    fn from(val: &EcCurve) -> Self {
+        match val {
+            EcCurve::P256 => "secp256r1".into(),
+            EcCurve::P384 => "secp384r1".into(),
+            EcCurve::P521 => "secp521r1".into(),
+        }
     }
 }
 
-impl<'a> Object<'a> for Builder<'a> {
-    fn class(env: &mut AttachGuard<'a>) -> JClass<'a> {
-        env.find_class("android/security/keystore/KeyGenParameterSpec$Builder")
-            .expect("Failed to find KeyGenParameterSpec$Builder class")
-    }
+/// A new `java.math.BigInteger` from unsigned big-endian bytes, not all zero
+fn unsigned_big_integer<'a>(magnitude: &[u8], env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+    static NEW_BIG_INTEGER: Constructor = Constructor::new(&BIG_INTEGER, "(I[B)V");
+
+    let bytes = env.byte_array_from_slice(magnitude)?;
+    // Signum 1, so that a leading bit set does not make the value negative
+    let value = NEW_BIG_INTEGER.new_object(&[JValue::Int(1), JValue::Object(&bytes)], env);
+    env.delete_local_ref(bytes)?;
+
+    value
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RsaKeyGenParams {
+    /// Java `int`, like [`Builder::set_key_size`]
+    pub bits: i32,
+    /// Usually 65537 (F4), non-zero, otherwise [`Error::OutOfRange`]
+    pub public_exponent: u64,
+}
+
+/// Parameters of the key to generate, passed to `setAlgorithmParameterSpec`
+#[derive(Debug, Clone, Copy)]
+pub enum AlgorithmParameterSpec {
+    /// `RSAKeyGenParameterSpec`
+    Rsa(RsaKeyGenParams),
+    /// `ECGenParameterSpec`
+    Ec(EcCurve),
+    /// `NamedParameterSpec.X25519`, API 33+
+    X25519,
+    /// `NamedParameterSpec.ED25519`, API 33+
+    Ed25519,
+}
+
+impl AlgorithmParameterSpec {
+    /// A new `java.security.spec.AlgorithmParameterSpec`
+    fn to_java<'a>(self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        static NEW_RSA_KEY_GEN_PARAMETER_SPEC: Constructor =
+            Constructor::new(&RSA_KEY_GEN_PARAMETER_SPEC, "(ILjava/math/BigInteger;)V");
+        static NEW_EC_GEN_PARAMETER_SPEC: Constructor =
+            Constructor::new(&EC_GEN_PARAMETER_SPEC, "(Ljava/lang/String;)V");
+        static NEW_NAMED_PARAMETER_SPEC: Constructor =
+            Constructor::new(&NAMED_PARAMETER_SPEC, "(Ljava/lang/String;)V");
+
+        if let AlgorithmParameterSpec::X25519 | AlgorithmParameterSpec::Ed25519 = self {
+            api_level::require(api_level::TIRAMISU, env)?;
+        }
+
+        env.with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| match self {
+            AlgorithmParameterSpec::Rsa(params) => {
+                if params.public_exponent == 0 {
+                    return Err(Error::OutOfRange("public_exponent"));
+                }
+                let public_exponent =
+                    unsigned_big_integer(&params.public_exponent.to_be_bytes(), env)?;
+
+                NEW_RSA_KEY_GEN_PARAMETER_SPEC.new_object(
+                    &[JValue::Int(params.bits), JValue::Object(&public_exponent)],
+                    env,
+                )
+            }
+            AlgorithmParameterSpec::Ec(curve) => {
+                let curve = env.new_string(&curve)?;
+
+                NEW_EC_GEN_PARAMETER_SPEC.new_object(&[JValue::Object(&curve)], env)
+            }
+            AlgorithmParameterSpec::X25519 => {
+                let name = env.new_string("X25519")?;
+
+                NEW_NAMED_PARAMETER_SPEC.new_object(&[JValue::Object(&name)], env)
+            }
+            AlgorithmParameterSpec::Ed25519 => {
+                let name = env.new_string("Ed25519")?;
 
-    fn l(&self) -> &JObject<'a> {
-        &self.0
+                NEW_NAMED_PARAMETER_SPEC.new_object(&[JValue::Object(&name)], env)
+            }
+        })
     }
 }
 
+#[repr(i32)]
+#[derive(Debug, Clone, Copy)]
+pub enum AuthType {
+    DeviceCredential = 1,
+    BiometricStrong = 2,
+}
+
+java_class! {
+    KeyGenParameterSpec(
+        KEY_GEN_PARAMETER_SPEC = "android/security/keystore/KeyGenParameterSpec"
+    )
+}
+
+java_class! {
+    Builder(BUILDER = "android/security/keystore/KeyGenParameterSpec$Builder")
+}
+
 impl<'a> Builder<'a> {
-    pub fn new<S>(alias: S, purposes: &[Purpose], env: &mut AttachGuard<'a>) -> Self
+    pub fn new<S>(alias: S, purposes: &[Purpose], env: &mut JNIEnv<'a>) -> Result<Self>
     where
         S: Into<JNIString>,
     {
-        let alias_str = env
-            .new_string(alias)
-            .expect("Failed to create alias string");
+        static NEW: Constructor = Constructor::new(&BUILDER, "(Ljava/lang/String;I)V");
+
+        api_level::require(api_level::M, env)?;
 
         let purposes: i32 = purposes.iter().fold(0, |acc, p| acc | *p as i32);
 
-        env.new_object(
-            "android/security/keystore/KeyGenParameterSpec$Builder",
-            "(Ljava/lang/String;I)V",
-            &[JValue::Object(&alias_str), JValue::Int(purposes)],
-        )
-        .expect("Failed to call new on KeyGenParameterSpec.Builder class")
-        .into()
-    }
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                let alias_str = env.new_string(alias)?;
 
-    pub fn set_digests(self, digests: &'a [Digest], env: &mut AttachGuard<'a>) -> Self {
-        let string_array = make_string_array(digests, env);
+                NEW.new_object(&[JValue::Object(&alias_str), JValue::Int(purposes)], env)
+            })?
+            .wrap())
+    }
 
-        env.call_method(
-            self.l(),
+    pub fn set_digests(self, digests: &[Digest], env: &mut JNIEnv<'a>) -> Result<Self> {
+        static SET_DIGESTS: Method = Method::new(
+            &BUILDER,
             "setDigests",
             "([Ljava/lang/String;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
-            &[JValue::Object(&string_array)],
-        )
-        .expect("Failed to call setDigests method")
-        .l()
-        .expect("Failed to get JObject")
-        .into()
+        );
+
+        call_with_strings(self.l(), &SET_DIGESTS, digests, env)?;
+
+        Ok(self)
     }
 
     pub fn set_encryption_paddings(
         self,
-        paddings: &'a [Padding],
-        env: &mut AttachGuard<'a>,
-    ) -> Self {
-        let string_array = make_string_array(paddings, env);
-
-        env.call_method(
-            self.l(),
+        paddings: &[Padding],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_ENCRYPTION_PADDINGS: Method = Method::new(
+            &BUILDER,
             "setEncryptionPaddings",
             "([Ljava/lang/String;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
-            &[JValue::Object(&string_array)],
-        )
-        .expect("Failed to call setEncryptionPaddings method")
-        .l()
-        .expect("Failed to get JObject")
-        .into()
+        );
+
+        call_with_strings(self.l(), &SET_ENCRYPTION_PADDINGS, paddings, env)?;
+
+        Ok(self)
+    }
+
+    /// Block modes the key can be used with, ECB is rejected with [`Error::InsecureParameter`]
+    ///
+    /// ECB leaks patterns of the plaintext, use [`Self::set_block_modes_allowing_ecb`] if a
+    /// legacy protocol really needs it.
+    pub fn set_block_modes(self, block_modes: &[BlockMode], env: &mut JNIEnv<'a>) -> Result<Self> {
+        check_block_modes(block_modes)?;
+
+        self.set_block_modes_allowing_ecb(block_modes, env)
+    }
+
+    /// [`Self::set_block_modes`], accepting ECB
+    pub fn set_block_modes_allowing_ecb(
+        self,
+        block_modes: &[BlockMode],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_BLOCK_MODES: Method = Method::new(
+            &BUILDER,
+            "setBlockModes",
+            "([Ljava/lang/String;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        call_with_strings(self.l(), &SET_BLOCK_MODES, block_modes, env)?;
+
+        Ok(self)
+    }
+
+    /// Requiring randomized encryption is the default, so that the same plaintext never
+    /// encrypts to the same ciphertext. `false` is rejected with [`Error::InsecureParameter`],
+    /// use [`Self::disable_randomized_encryption`] to opt out.
+    pub fn set_randomized_encryption_required(
+        self,
+        required: bool,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        check_randomized_encryption_required(required)?;
+
+        self.call_set_randomized_encryption_required(required, env)
+    }
+
+    /// Allows deterministic encryption, e.g. ECB or caller-provided IVs
+    pub fn disable_randomized_encryption(self, env: &mut JNIEnv<'a>) -> Result<Self> {
+        self.call_set_randomized_encryption_required(false, env)
+    }
+
+    fn call_set_randomized_encryption_required(
+        self,
+        required: bool,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_RANDOMIZED_ENCRYPTION_REQUIRED: Method = Method::new(
+            &BUILDER,
+            "setRandomizedEncryptionRequired",
+            "(Z)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            SET_RANDOMIZED_ENCRYPTION_REQUIRED.call(
+                self.l(),
+                &[JValue::Bool(required as u8)],
+                env,
+            )?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    pub fn set_signature_paddings(
+        self,
+        paddings: &[SignaturePadding],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_SIGNATURE_PADDINGS: Method = Method::new(
+            &BUILDER,
+            "setSignaturePaddings",
+            "([Ljava/lang/String;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        call_with_strings(self.l(), &SET_SIGNATURE_PADDINGS, paddings, env)?;
+
+        Ok(self)
     }
 
     pub fn set_user_authentication_parameters(
         self,
         timeout: usize,
         auth_type: &[AuthType],
-        env: &mut AttachGuard<'a>,
-    ) -> Self {
-        env.call_method(
-            self.l(),
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_USER_AUTHENTICATION_PARAMETERS: Method = Method::new(
+            &BUILDER,
             "setUserAuthenticationParameters",
             "(II)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
-            &[
-                JValue::Int(timeout as i32),
-                JValue::Int(auth_type.iter().fold(0, |acc, p| acc | *p as i32)),
-            ],
-        )
-        .expect("Failed to call setUserAuthenticationParameters method")
-        .l()
-        .expect("Failed to get JObject")
-        .into()
+        );
+
+        api_level::require(api_level::R, env)?;
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            SET_USER_AUTHENTICATION_PARAMETERS.call(
+                self.l(),
+                &[
+                    JValue::Int(timeout as i32),
+                    JValue::Int(auth_type.iter().fold(0, |acc, p| acc | *p as i32)),
+                ],
+                env,
+            )?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
     }
 
     pub fn set_user_authentication_required(
         self,
         required: bool,
-        env: &mut AttachGuard<'a>,
-    ) -> Self {
-        env.call_method(
-            self.l(),
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_USER_AUTHENTICATION_REQUIRED: Method = Method::new(
+            &BUILDER,
             "setUserAuthenticationRequired",
             "(Z)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
-            &[JValue::Bool(required as u8)],
-        )
-        .expect("Failed to call setUserAuthenticationRequired method")
-        .l()
-        .expect("Failed to get JObject")
-        .into()
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            SET_USER_AUTHENTICATION_REQUIRED.call(
+                self.l(),
+                &[JValue::Bool(required as u8)],
+                env,
+            )?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    pub fn set_max_usage_count(self, count: i32, env: &mut JNIEnv<'a>) -> Result<Self> {
+        static SET_MAX_USAGE_COUNT: Method = Method::new(
+            &BUILDER,
+            "setMaxUsageCount",
+            "(I)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        api_level::require(api_level::S, env)?;
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            SET_MAX_USAGE_COUNT.call(self.l(), &[JValue::Int(count)], env)?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    /// Key size in bits, or the curve size for EC keys
+    pub fn set_key_size(self, key_size: i32, env: &mut JNIEnv<'a>) -> Result<Self> {
+        static SET_KEY_SIZE: Method = Method::new(
+            &BUILDER,
+            "setKeySize",
+            "(I)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            SET_KEY_SIZE.call(self.l(), &[JValue::Int(key_size)], env)?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    pub fn set_algorithm_parameter_spec(
+        self,
+        spec: AlgorithmParameterSpec,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_ALGORITHM_PARAMETER_SPEC: Method = Method::new(
+            &BUILDER,
+            "setAlgorithmParameterSpec",
+            "(Ljava/security/spec/AlgorithmParameterSpec;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let spec = spec.to_java(env)?;
+            SET_ALGORITHM_PARAMETER_SPEC.call(self.l(), &[JValue::Object(&spec)], env)?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    /// Subject of the self-signed certificate of generated key pairs, `CN=fake` by default
+    pub fn set_certificate_subject(
+        self,
+        subject: &DistinguishedName,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static NEW_X500_PRINCIPAL: Constructor =
+            Constructor::new(&X500_PRINCIPAL, "(Ljava/lang/String;)V");
+        static SET_CERTIFICATE_SUBJECT: Method = Method::new(
+            &BUILDER,
+            "setCertificateSubject",
+            "(Ljavax/security/auth/x500/X500Principal;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let name = env.new_string(subject.to_rfc2253())?;
+            let principal = NEW_X500_PRINCIPAL.new_object(&[JValue::Object(&name)], env)?;
+
+            SET_CERTIFICATE_SUBJECT.call(self.l(), &[JValue::Object(&principal)], env)?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    /// Serial number of the self-signed certificate, as unsigned big-endian bytes
+    ///
+    /// The serial must be non-zero, an empty or all-zero `serial` fails with
+    /// [`Error::OutOfRange`].
+    pub fn set_certificate_serial_number(
+        self,
+        serial: &[u8],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_CERTIFICATE_SERIAL_NUMBER: Method = Method::new(
+            &BUILDER,
+            "setCertificateSerialNumber",
+            "(Ljava/math/BigInteger;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        if serial.iter().all(|b| *b == 0) {
+            return Err(Error::OutOfRange("serial"));
+        }
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let serial = unsigned_big_integer(serial, env)?;
+
+            SET_CERTIFICATE_SERIAL_NUMBER.call(self.l(), &[JValue::Object(&serial)], env)?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self)
+    }
+
+    pub fn set_certificate_not_before(
+        self,
+        not_before: SystemTime,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_CERTIFICATE_NOT_BEFORE: Method = Method::new(
+            &BUILDER,
+            "setCertificateNotBefore",
+            "(Ljava/util/Date;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        call_with_date(self.l(), &SET_CERTIFICATE_NOT_BEFORE, not_before, env)?;
+
+        Ok(self)
+    }
+
+    pub fn set_certificate_not_after(
+        self,
+        not_after: SystemTime,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_CERTIFICATE_NOT_AFTER: Method = Method::new(
+            &BUILDER,
+            "setCertificateNotAfter",
+            "(Ljava/util/Date;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        call_with_date(self.l(), &SET_CERTIFICATE_NOT_AFTER, not_after, env)?;
+
+        Ok(self)
+    }
+
+    /// Date from which the key can be used
+    pub fn set_key_validity_start(self, start: SystemTime, env: &mut JNIEnv<'a>) -> Result<Self> {
+        static SET_KEY_VALIDITY_START: Method = Method::new(
+            &BUILDER,
+            "setKeyValidityStart",
+            "(Ljava/util/Date;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        call_with_date(self.l(), &SET_KEY_VALIDITY_START, start, env)?;
+
+        Ok(self)
     }
 
-    pub fn build(self, env: &mut AttachGuard<'a>) -> KeyGenParameterSpec<'a> {
-        env.call_method(
-            self.l(),
+    /// Date after which the key can no longer encrypt or sign, it can still decrypt and verify
+    pub fn set_key_validity_for_origination_end(
+        self,
+        end: SystemTime,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_KEY_VALIDITY_FOR_ORIGINATION_END: Method = Method::new(
+            &BUILDER,
+            "setKeyValidityForOriginationEnd",
+            "(Ljava/util/Date;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        call_with_date(self.l(), &SET_KEY_VALIDITY_FOR_ORIGINATION_END, end, env)?;
+
+        Ok(self)
+    }
+
+    /// Date after which the key can no longer decrypt or verify
+    pub fn set_key_validity_for_consumption_end(
+        self,
+        end: SystemTime,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static SET_KEY_VALIDITY_FOR_CONSUMPTION_END: Method = Method::new(
+            &BUILDER,
+            "setKeyValidityForConsumptionEnd",
+            "(Ljava/util/Date;)Landroid/security/keystore/KeyGenParameterSpec$Builder;",
+        );
+
+        call_with_date(self.l(), &SET_KEY_VALIDITY_FOR_CONSUMPTION_END, end, env)?;
+
+        Ok(self)
+    }
+
+    pub fn build(self, env: &mut JNIEnv<'a>) -> Result<KeyGenParameterSpec<'a>> {
+        static BUILD: Method = Method::new(
+            &BUILDER,
             "build",
             "()Landroid/security/keystore/KeyGenParameterSpec;",
-            &[],
-        )
-        .expect("Failed to call build method")
-        .l()
-        .expect("Failed to get JObject")
-        .into()
+        );
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(BUILD.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
     }
 }
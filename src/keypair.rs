@@ -1,166 +1,208 @@
 use base64::Engine;
-use jni::objects::{JByteArray, JValue};
-
-use crate::{AttachGuard, JClass, JObject, Object, keypair_generator::Algorithm};
-
-#[derive(Debug)]
-pub struct KeyPair<'a>(JObject<'a>);
-
-impl<'a> From<JObject<'a>> for KeyPair<'a> {
-    fn from(value: JObject<'a>) -> Self {
-        Self(value)
-    }
+use jni::objects::{JByteArray, JString, JValue};
+
+use crate::{
+    Error, JNIEnv, LOCAL_FRAME_CAPACITY, Object, Result, api_level,
+    cache::{Class, Constructor, Method, StaticMethod},
+    certificate::Certificate,
+    keypair_generator::Algorithm,
+    sealed::Wrap,
+};
+
+static KEY_FACTORY: Class = Class::new("java/security/KeyFactory");
+static X509_ENCODED_KEY_SPEC: Class = Class::new("java/security/spec/X509EncodedKeySpec");
+static PKCS8_ENCODED_KEY_SPEC: Class = Class::new("java/security/spec/PKCS8EncodedKeySpec");
+static SIGNATURE: Class = Class::new("java/security/Signature");
+static KEY: Class = Class::new("java/security/Key");
+
+java_class! {
+    KeyPair(KEY_PAIR = "java/security/KeyPair"), owned OwnedKeyPair
 }
 
-#[derive(Debug)]
-pub struct PublicKey<'a>(JObject<'a>);
-
-impl<'a> From<JObject<'a>> for PublicKey<'a> {
-    fn from(value: JObject<'a>) -> Self {
-        Self(value)
-    }
+java_class! {
+    PublicKey(PUBLIC_KEY = "java/security/PublicKey"), owned OwnedPublicKey
 }
 
-#[derive(Debug)]
-pub struct PrivateKey<'a>(JObject<'a>);
-
-impl<'a> From<JObject<'a>> for PrivateKey<'a> {
-    fn from(value: JObject<'a>) -> Self {
-        Self(value)
-    }
+java_class! {
+    PrivateKey(PRIVATE_KEY = "java/security/PrivateKey"), owned OwnedPrivateKey
 }
+
 impl<'a> KeyPair<'a> {
-    pub fn get_public(&self, env: &mut AttachGuard<'a>) -> jni::errors::Result<PublicKey<'a>> {
+    pub fn get_public(&self, env: &mut JNIEnv<'a>) -> Result<PublicKey<'a>> {
+        static GET_PUBLIC: Method =
+            Method::new(&KEY_PAIR, "getPublic", "()Ljava/security/PublicKey;");
+
         Ok(env
-            .call_method(self.l(), "getPublic", "()Ljava/security/PublicKey;", &[])
-            .unwrap()
-            .l()?
-            .into())
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(GET_PUBLIC.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
     }
 
-    pub fn get_private(&self, env: &mut AttachGuard<'a>) -> jni::errors::Result<PrivateKey<'a>> {
+    pub fn get_private(&self, env: &mut JNIEnv<'a>) -> Result<PrivateKey<'a>> {
+        static GET_PRIVATE: Method =
+            Method::new(&KEY_PAIR, "getPrivate", "()Ljava/security/PrivateKey;");
+
         Ok(env
-            .call_method(self.l(), "getPrivate", "()Ljava/security/PrivateKey;", &[])
-            .unwrap()
-            .l()?
-            .into())
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(GET_PRIVATE.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
     }
 }
 
-impl<'a> Object<'a> for KeyPair<'a> {
-    fn class(env: &mut AttachGuard<'a>) -> JClass<'a> {
-        env.find_class("java/security/KeyPair")
-            .expect("Failed to find KeyPair class")
-    }
+impl<'a> PublicKey<'a> {
+    /// The key algorithm, e.g. `RSA` or `EC`
+    pub fn get_algorithm(&self, env: &mut JNIEnv<'a>) -> Result<String> {
+        static GET_ALGORITHM: Method = Method::new(&KEY, "getAlgorithm", "()Ljava/lang/String;");
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let algorithm: JString = GET_ALGORITHM.call(self.l(), &[], env)?.l()?.into();
 
-    fn l(&self) -> &JObject<'a> {
-        &self.0
+            Ok(env.get_string(&algorithm)?.into())
+        })
     }
-}
 
-impl<'a> PublicKey<'a> {
-    pub fn get_decoded(&self, env: &mut AttachGuard<'a>) -> String {
-        let public_key_bytes: JByteArray<'_> = env
-            .call_method(self.l(), "getEncoded", "()[B", &[])
-            .expect("Failed to call getEncoded")
-            .l()
-            .expect("Failed to get byte array")
-            .into();
-
-        let public_key_bytes = env
-            .convert_byte_array(public_key_bytes)
-            .expect("Failed to convert byte array");
+    pub fn get_decoded(&self, env: &mut JNIEnv<'a>) -> Result<String> {
+        static GET_ENCODED: Method = Method::new(&PUBLIC_KEY, "getEncoded", "()[B");
+
+        let public_key_bytes = env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let public_key_bytes: JByteArray = GET_ENCODED.call(self.l(), &[], env)?.l()?.into();
+
+            Ok::<_, Error>(env.convert_byte_array(public_key_bytes)?)
+        })?;
 
         let engine: base64::engine::GeneralPurpose = base64::engine::general_purpose::STANDARD;
-        engine.encode(public_key_bytes)
+        Ok(engine.encode(public_key_bytes))
     }
 
     pub fn from_x509_string(
         str: impl Into<String>,
         algorithm: Algorithm,
-        env: &mut AttachGuard<'a>,
-    ) -> Self {
-        let engine: base64::engine::GeneralPurpose = base64::engine::general_purpose::STANDARD;
-        let bytes = engine.decode(str.into()).expect("Failed to decode string");
-
-        let java_byte_array = env
-            .new_byte_array(bytes.len() as i32)
-            .expect("Failed to create byte array");
-
-        env.set_byte_array_region(
-            &java_byte_array,
-            0,
-            bytes
-                .iter()
-                .map(|b| *b as i8)
-                .collect::<Vec<i8>>()
-                .as_slice(),
-        )
-        .expect("Failed to set byte array region");
-
-        let key_factory_class = env
-            .find_class("java/security/KeyFactory")
-            .expect("Failed to find KeyFactory class");
-
-        let algorithm = &env.new_string(&algorithm).expect("Failed to create string");
-        let key_factory = env
-            .call_static_method(
-                key_factory_class,
-                "getInstance",
-                "(Ljava/lang/String;)Ljava/security/KeyFactory;",
-                &[JValue::Object(algorithm)],
-            )
-            .expect("Failed to create KeyFactory")
-            .l()
-            .expect("Failed to get Object");
-
-        let spec_public = env
-            .new_object(
-                "java/security/spec/X509EncodedKeySpec",
-                "([B)V",
-                &[JValue::Object(&java_byte_array)],
-            )
-            .expect("Failed to create EncodedKeySpec");
-
-        let key = env.call_method(
-            &key_factory,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static GET_INSTANCE: StaticMethod = StaticMethod::new(
+            &KEY_FACTORY,
+            "getInstance",
+            "(Ljava/lang/String;)Ljava/security/KeyFactory;",
+        );
+        static NEW_X509_ENCODED_KEY_SPEC: Constructor =
+            Constructor::new(&X509_ENCODED_KEY_SPEC, "([B)V");
+        static GENERATE_PUBLIC: Method = Method::new(
+            &KEY_FACTORY,
             "generatePublic",
             "(Ljava/security/spec/KeySpec;)Ljava/security/PublicKey;",
-            &[JValue::Object(&spec_public)],
         );
 
-        if env.exception_check().expect("Failed to check exception") {
-            env.exception_describe()
-                .expect("Failed to describe exception");
-        }
+        api_level::require(algorithm.min_api_level(), env)?;
 
-        let key = key
-            .expect("Failed to generate public key")
-            .l()
-            .expect("Failed to get public key");
+        let engine: base64::engine::GeneralPurpose = base64::engine::general_purpose::STANDARD;
+        let bytes = engine.decode(str.into())?;
 
-        key.into()
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                let java_byte_array = env.byte_array_from_slice(&bytes)?;
+
+                let algorithm = &env.new_string(&algorithm)?;
+                let key_factory = GET_INSTANCE.call(&[JValue::Object(algorithm)], env)?.l()?;
+
+                let spec_public = NEW_X509_ENCODED_KEY_SPEC
+                    .new_object(&[JValue::Object(&java_byte_array)], env)?;
+
+                Ok::<_, Error>(
+                    GENERATE_PUBLIC
+                        .call(&key_factory, &[JValue::Object(&spec_public)], env)?
+                        .l()?,
+                )
+            })?
+            .wrap())
     }
 }
 
-impl<'a> Object<'a> for PublicKey<'a> {
-    fn class(env: &mut AttachGuard<'a>) -> JClass<'a> {
-        env.find_class("java/security/PublicKey")
-            .expect("Failed to find PublicKey class")
-    }
-
-    fn l(&self) -> &JObject<'a> {
-        &self.0
-    }
-}
+impl<'a> PrivateKey<'a> {
+    /// Parses a PKCS#8 encoded private key, outside of any keystore
+    pub fn from_pkcs8(der: &[u8], algorithm: Algorithm, env: &mut JNIEnv<'a>) -> Result<Self> {
+        static GET_INSTANCE: StaticMethod = StaticMethod::new(
+            &KEY_FACTORY,
+            "getInstance",
+            "(Ljava/lang/String;)Ljava/security/KeyFactory;",
+        );
+        static NEW_PKCS8_ENCODED_KEY_SPEC: Constructor =
+            Constructor::new(&PKCS8_ENCODED_KEY_SPEC, "([B)V");
+        static GENERATE_PRIVATE: Method = Method::new(
+            &KEY_FACTORY,
+            "generatePrivate",
+            "(Ljava/security/spec/KeySpec;)Ljava/security/PrivateKey;",
+        );
 
-impl<'a> Object<'a> for PrivateKey<'a> {
-    fn class(env: &mut AttachGuard<'a>) -> JClass<'a> {
-        env.find_class("java/security/PrivateKey")
-            .expect("Failed to find PrivateKey class")
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                let java_byte_array = env.byte_array_from_slice(der)?;
+
+                let algorithm = &env.new_string(&algorithm)?;
+                let key_factory = GET_INSTANCE.call(&[JValue::Object(algorithm)], env)?.l()?;
+
+                let spec_private = NEW_PKCS8_ENCODED_KEY_SPEC
+                    .new_object(&[JValue::Object(&java_byte_array)], env)?;
+
+                Ok::<_, Error>(
+                    GENERATE_PRIVATE
+                        .call(&key_factory, &[JValue::Object(&spec_private)], env)?
+                        .l()?,
+                )
+            })?
+            .wrap())
     }
 
-    fn l(&self) -> &JObject<'a> {
-        &self.0
+    /// Whether this key is the private half of the public key in `certificate`
+    ///
+    /// Checked by signing a fixed message and verifying it with the certificate, which works
+    /// for any RSA or EC key without parsing the key material.
+    pub fn matches_certificate(
+        &self,
+        certificate: &Certificate<'a>,
+        algorithm: Algorithm,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<bool> {
+        static GET_INSTANCE: StaticMethod = StaticMethod::new(
+            &SIGNATURE,
+            "getInstance",
+            "(Ljava/lang/String;)Ljava/security/Signature;",
+        );
+        static INIT_SIGN: Method =
+            Method::new(&SIGNATURE, "initSign", "(Ljava/security/PrivateKey;)V");
+        static INIT_VERIFY: Method = Method::new(
+            &SIGNATURE,
+            "initVerify",
+            "(Ljava/security/cert/Certificate;)V",
+        );
+        static UPDATE: Method = Method::new(&SIGNATURE, "update", "([B)V");
+        static SIGN: Method = Method::new(&SIGNATURE, "sign", "()[B");
+        static VERIFY: Method = Method::new(&SIGNATURE, "verify", "([B)Z");
+
+        let signature_algorithm = match algorithm {
+            Algorithm::RSA => "SHA256withRSA",
+            Algorithm::EC => "SHA256withECDSA",
+            _ => return Err(Error::UnsupportedAlgorithm(format!("{algorithm:?}"))),
+        };
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let signature_algorithm = env.new_string(signature_algorithm)?;
+            let signature = GET_INSTANCE
+                .call(&[JValue::Object(&signature_algorithm)], env)?
+                .l()?;
+            let message = env.byte_array_from_slice(b"android-keystore key pair check")?;
+
+            INIT_SIGN.call(&signature, &[JValue::Object(self.l())], env)?;
+            UPDATE.call(&signature, &[JValue::Object(&message)], env)?;
+            let signed = SIGN.call(&signature, &[], env)?.l()?;
+
+            INIT_VERIFY.call(&signature, &[JValue::Object(certificate.l())], env)?;
+            UPDATE.call(&signature, &[JValue::Object(&message)], env)?;
+
+            Ok(VERIFY
+                .call(&signature, &[JValue::Object(&signed)], env)?
+                .z()?)
+        })
     }
 }
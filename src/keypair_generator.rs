@@ -1,28 +1,40 @@
-use jni::{AttachGuard, strings::JNIString};
+use jni::{JNIEnv, strings::JNIString};
 
 use crate::{
-    JClass, JObject, JValue, Object, keygen_parameter_spec::KeyGenParameterSpec, keypair::KeyPair,
+    Error, JValue, LOCAL_FRAME_CAPACITY, Object, Result, api_level,
+    cache::{Method, StaticMethod},
+    keygen_parameter_spec::KeyGenParameterSpec,
+    keypair::KeyPair,
+    sealed::Wrap,
 };
 
+#[derive(Debug, Clone, Copy)]
 pub enum Algorithm {
     DH,
     DSA,
-    #[cfg(feature = "api_level_11")]
     EC,
     RSA,
-    #[cfg(feature = "api_level_33")]
     XDH,
 }
 
+impl Algorithm {
+    /// Minimum API level on which the algorithm is available
+    pub fn min_api_level(&self) -> u32 {
+        match self {
+            Algorithm::EC => api_level::HONEYCOMB,
+            Algorithm::XDH => api_level::TIRAMISU,
+            _ => 1,
+        }
+    }
+}
+
 impl From<&Algorithm> for JNIString {
     fn from(val: &Algorithm) -> Self {
         match val {
             Algorithm::DH => "DH".into(),
             Algorithm::DSA => "DSA".into(),
-            #[cfg(feature = "api_level_11")]
             Algorithm::EC => "EC".into(),
             Algorithm::RSA => "RSA".into(),
-            #[cfg(feature = "api_level_33")]
             Algorithm::XDH => "XDH".into(),
         }
     }
@@ -41,98 +53,72 @@ impl From<&Provider> for JNIString {
     }
 }
 
-#[derive(Debug)]
-pub enum Exception {
-    NoSuchAlgorithmException(jni::errors::Error),
-    InvalidAlgorithmParameterException(jni::errors::Error),
-}
-
-/// A wrapper around a JObject representing a KeyPairGenerator instance
-/// KeyPairGenerator being a singleton, it must be created using the `get_instance()` method
-/// The instance obtained using `get_instance()` can then be used to generate a keypair
-#[derive(Debug)]
-pub struct KeyPairGenerator<'a>(JObject<'a>);
-
-impl<'a> From<JObject<'a>> for KeyPairGenerator<'a> {
-    fn from(value: JObject<'a>) -> Self {
-        Self(value)
-    }
+java_class! {
+    /// A wrapper around a JObject representing a KeyPairGenerator instance
+    /// KeyPairGenerator being a singleton, it must be created using the `get_instance()` method
+    /// The instance obtained using `get_instance()` can then be used to generate a keypair
+    KeyPairGenerator(KEY_PAIR_GENERATOR = "java/security/KeyPairGenerator"),
+    owned OwnedKeyPairGenerator
 }
 
 impl<'a> KeyPairGenerator<'a> {
     pub fn get_instance(
         algorithm: Algorithm,
         provider: Provider,
-        env: &mut AttachGuard<'a>,
-    ) -> Result<Self, Exception> {
-        let keypair_generator_class = env
-            .find_class("java/security/KeyPairGenerator")
-            .expect("Failed to find KeyPairGenerator class");
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static GET_INSTANCE: StaticMethod = StaticMethod::new(
+            &KEY_PAIR_GENERATOR,
+            "getInstance",
+            "(Ljava/lang/String;Ljava/lang/String;)Ljava/security/KeyPairGenerator;",
+        );
 
-        let algorithm = &env
-            .new_string(&algorithm)
-            .expect("Cannot create string for algorithm");
-
-        let provider = &env
-            .new_string(&provider)
-            .expect("Cannot create string for provider");
+        api_level::require(algorithm.min_api_level(), env)?;
 
         Ok(env
-            .call_static_method(
-                keypair_generator_class,
-                "getInstance",
-                "(Ljava/lang/String;Ljava/lang/String;)Ljava/security/KeyPairGenerator;",
-                &[JValue::Object(algorithm), JValue::Object(provider)],
-            )
-            .map_err(Exception::NoSuchAlgorithmException)?
-            .l()
-            .expect("Failed to get JObject")
-            .into())
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                let algorithm = &env.new_string(&algorithm)?;
+
+                let provider = &env.new_string(&provider)?;
+
+                Ok::<_, Error>(
+                    GET_INSTANCE
+                        .call(&[JValue::Object(algorithm), JValue::Object(provider)], env)?
+                        .l()?,
+                )
+            })?
+            .wrap())
     }
 
     pub fn initialize(
         &self,
         keygen_parameter_spec: KeyGenParameterSpec<'a>,
-        env: &mut AttachGuard<'a>,
-    ) -> Result<(), Exception> {
-        let res = env.call_method(
-            self.l(),
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()> {
+        static INITIALIZE: Method = Method::new(
+            &KEY_PAIR_GENERATOR,
             "initialize",
             "(Ljava/security/spec/AlgorithmParameterSpec;)V",
-            &[JValue::Object(keygen_parameter_spec.l())],
-        )
-        // .map_err(Exception::InvalidAlgorithmParameterException)?
-        ;
-
-        if env.exception_check().unwrap() {
-            env.exception_describe().unwrap();
-            res.map_err(Exception::InvalidAlgorithmParameterException)?;
-        }
+        );
 
-        Ok(())
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            INITIALIZE.call(self.l(), &[JValue::Object(keygen_parameter_spec.l())], env)?;
+
+            Ok(())
+        })
     }
 
-    pub fn generate_keypair(&self, env: &mut AttachGuard<'a>) -> KeyPair<'a> {
-        env.call_method(
-            self.l(),
+    pub fn generate_keypair(&self, env: &mut JNIEnv<'a>) -> Result<KeyPair<'a>> {
+        static GENERATE_KEY_PAIR: Method = Method::new(
+            &KEY_PAIR_GENERATOR,
             "generateKeyPair",
             "()Ljava/security/KeyPair;",
-            &[],
-        )
-        .expect("Failed to call generateKeyPair")
-        .l()
-        .expect("Failed to get JObject")
-        .into()
-    }
-}
-
-impl<'a> Object<'a> for KeyPairGenerator<'a> {
-    fn class(env: &mut AttachGuard<'a>) -> JClass<'a> {
-        env.find_class("java/security/KeyPairGenerator")
-            .expect("Failed to find KeyPairGenerator class")
-    }
+        );
 
-    fn l(&self) -> &JObject<'a> {
-        &self.0
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(GENERATE_KEY_PAIR.call(self.l(), &[], env)?.l()?)
+            })?
+            .wrap())
     }
 }
@@ -1,176 +1,623 @@
+//! Rust wrappers around the Android KeyStore Java APIs
+//!
+//! Every API takes a `&mut JNIEnv`, so it can be called with the `JNIEnv` handed to an
+//! `extern "system"` native method as well as with the `AttachGuard` returned by
+//! [`with_jni_env`] or `JavaVM::attach_current_thread`, which derefs to it.
+
+use std::{sync::OnceLock, time::SystemTime};
+
 use jni::{
-    AttachGuard,
-    objects::{JClass, JObject, JString, JValue},
+    JNIEnv,
+    objects::{JClass, JObject, JObjectArray, JString, JValue, JValueOwned},
     strings::JNIString,
 };
 
+#[macro_use]
+mod macros;
+
+pub mod aliases;
+pub mod api_level;
+mod cache;
+pub mod certificate;
+pub mod context;
+mod der;
+pub mod distinguished_name;
+pub mod entry;
+pub mod error;
+pub mod exception;
+pub mod key_generator;
+pub mod key_protection;
 pub mod keygen_parameter_spec;
 pub mod keypair;
 pub mod keypair_generator;
+pub mod namespaced;
+pub mod secret_key;
 pub mod utils;
-pub use keypair::PrivateKey;
-pub use utils::with_jni_env;
+pub mod wrapped_key;
+pub use aliases::Aliases;
+pub use api_level::api_level;
+use cache::{Class, Method, StaticMethod};
+pub use certificate::Certificate;
+use certificate::chain_to_der;
+pub use context::Context;
+pub use distinguished_name::DistinguishedName;
+pub use entry::{
+    Entry, OwnedPrivateKeyEntry, PrivateKeyEntry, SecretKeyEntry, TrustedCertificateEntry,
+};
+pub use error::{Error, Recovery, Result};
+pub use exception::JavaException;
+use key_protection::KeyProtection;
+pub use keypair::{OwnedPrivateKey, PrivateKey};
+use keypair_generator::Algorithm;
+pub use namespaced::NamespacedKeyStore;
+use sealed::Wrap;
+pub use secret_key::{SecretKey, SecretKeyAlgorithm};
+use utils::{LOCAL_FRAME_CAPACITY, date_to_system_time};
+pub use utils::{init, with_jni_env};
+use wrapped_key::{OaepSpec, WrappedKeyEntry};
+
+/// Entry points for `benches/jni_lookups.rs`, not part of the API
+#[doc(hidden)]
+#[cfg(feature = "desktop-jvm")]
+pub mod bench {
+    use jni::{JNIEnv, objects::JObject};
+
+    use crate::Aliases;
+
+    /// The walk behind [`crate::AndroidKeyStore::iter_aliases`], over any `Enumeration` of
+    /// strings, as the `AndroidKeyStore` provider only exists on Android
+    pub fn iter_enumeration<'e, 'a>(
+        enumeration: JObject<'a>,
+        env: &'e mut JNIEnv<'a>,
+    ) -> Aliases<'e, 'a> {
+        Aliases::new(enumeration, env)
+    }
+}
+
+static OBJECT: Class = Class::new("java/lang/Object");
+static ENUMERATION: Class = Class::new("java/util/Enumeration");
+static TO_STRING: Method = Method::new(&OBJECT, "toString", "()Ljava/lang/String;");
+
+pub(crate) mod sealed {
+    use jni::objects::JObject;
+
+    /// Unchecked construction of a wrapper, kept out of reach of other crates so that a wrapper
+    /// always holds an instance of its class, which the unchecked JNI calls rely on
+    pub trait FromRaw<'a> {
+        fn from_raw(obj: JObject<'a>) -> Self;
+    }
+
+    /// Wraps the return value of a Java method declared to return the wrapper's class
+    pub(crate) trait Wrap<'a> {
+        fn wrap<T: FromRaw<'a>>(self) -> T;
+    }
+
+    impl<'a> Wrap<'a> for JObject<'a> {
+        fn wrap<T: FromRaw<'a>>(self) -> T {
+            T::from_raw(self)
+        }
+    }
+}
 
 pub trait Object<'a> {
-    fn class(env: &mut AttachGuard<'a>) -> JClass<'a>;
+    /// JNI path of the Java class the wrapped object is an instance of
+    const CLASS_NAME: &'static str;
+
+    fn class(env: &mut JNIEnv<'a>) -> Result<JClass<'a>>;
 
     fn l(&self) -> &JObject<'a>;
 
-    fn to_jstring(&self, env: &mut AttachGuard<'a>) -> jni::errors::Result<JString<'a>> {
+    fn into_inner(self) -> JObject<'a>;
+
+    /// Whether `obj` is a non-null instance of [`Self::CLASS_NAME`]
+    fn is_instance(obj: &JObject<'_>, env: &mut JNIEnv<'a>) -> Result<bool> {
+        if obj.is_null() {
+            return Ok(false);
+        }
+
+        let class = Self::class(env)?;
+        let is_instance = env.is_instance_of(obj, &class);
+        env.delete_local_ref(class)?;
+
+        Ok(is_instance?)
+    }
+
+    /// Converts this wrapper into another one, checking the class of the wrapped object
+    fn downcast<T>(self, env: &mut JNIEnv<'a>) -> Result<T>
+    where
+        Self: Sized,
+        T: Cast<'a>,
+    {
+        T::try_cast(self.into_inner(), env)
+    }
+
+    fn to_jstring(&self, env: &mut JNIEnv<'a>) -> Result<JString<'a>> {
         Ok(env
-            .call_method(self.l(), "toString", "()Ljava/lang/String;", &[])?
-            .l()?
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                Ok::<_, Error>(TO_STRING.call(self.l(), &[], env)?.l()?)
+            })?
             .into())
     }
 
-    fn to_jni_string(&self, env: &mut AttachGuard<'a>) -> jni::errors::Result<JNIString> {
-        let jstring = unsafe { JString::from_raw(**self.to_jstring(env)?) };
-        Ok(env.get_string(jstring.as_ref())?.to_owned())
+    fn to_jni_string(&self, env: &mut JNIEnv<'a>) -> Result<JNIString> {
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let jstring: JString = TO_STRING.call(self.l(), &[], env)?.l()?.into();
+            Ok(env.get_string(&jstring)?.to_owned())
+        })
     }
 }
 
+/// Checked construction of a wrapper from a `JObject` of unknown class
+pub trait Cast<'a>: Object<'a> + sealed::FromRaw<'a> + Sized {
+    /// Wraps `obj`, checking that it is a non-null instance of [`Object::CLASS_NAME`]
+    fn try_cast(obj: JObject<'a>, env: &mut JNIEnv<'a>) -> Result<Self> {
+        if Self::is_instance(&obj, env)? {
+            return Ok(Self::from_raw(obj));
+        }
+
+        Err(Error::ClassCast {
+            expected: Self::CLASS_NAME,
+            actual: utils::class_name(&obj, env)?,
+        })
+    }
+}
+
+/// The `AndroidKeyStore` `java.security.KeyStore`, before `load()`
+///
+/// A `KeyStore` throws on every query until it is loaded, so the query APIs are only available
+/// on the [`AndroidKeyStore`] returned by [`UnloadedKeyStore::load`].
 #[derive(Debug)]
-pub struct AndroidKeyStore<'a>(JObject<'a>);
+pub struct UnloadedKeyStore<'a>(AndroidKeyStore<'a>);
+
+impl<'a> UnloadedKeyStore<'a> {
+    pub fn load(self, env: &mut JNIEnv<'a>) -> Result<AndroidKeyStore<'a>> {
+        static LOAD: Method = Method::new(
+            &KEY_STORE,
+            "load",
+            "(Ljava/security/KeyStore$LoadStoreParameter;)V",
+        );
 
-impl<'a> From<JObject<'a>> for AndroidKeyStore<'a> {
-    fn from(value: JObject<'a>) -> Self {
-        AndroidKeyStore(value)
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            LOAD.call(self.0.l(), &[JValue::Object(&JObject::null())], env)?;
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(self.0)
     }
 }
 
+java_class! {
+    /// A wrapper around a JObject representing the `AndroidKeyStore` `java.security.KeyStore`, loaded
+    ///
+    /// Only built by [`UnloadedKeyStore::load`] and [`OwnedAndroidKeyStore::as_local`], there is
+    /// no `try_cast` as the class does not tell whether the keystore is loaded.
+    sealed AndroidKeyStore(KEY_STORE = "java/security/KeyStore"), owned OwnedAndroidKeyStore
+}
+
 impl<'a> AndroidKeyStore<'a> {
-    pub fn get_instance(env: &mut AttachGuard<'a>) -> AndroidKeyStore<'a> {
-        let keystore_class = env
-            .find_class("java/security/KeyStore")
-            .expect("Failed to find KeyStore class");
-        let android_key_store_string = env
-            .new_string("AndroidKeyStore")
-            .expect("Failed to create AndroidKeyStore string");
-
-        env.call_static_method(
-            keystore_class,
+    pub fn get_instance(env: &mut JNIEnv<'a>) -> Result<UnloadedKeyStore<'a>> {
+        static GET_INSTANCE: StaticMethod = StaticMethod::new(
+            &KEY_STORE,
             "getInstance",
             "(Ljava/lang/String;)Ljava/security/KeyStore;",
-            &[JValue::Object(&android_key_store_string)],
-        )
-        .expect("Failed to call getInstance() method")
-        .l()
-        .expect("Failed to get JObject()")
-        .into()
+        );
+
+        let key_store = env.with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+            let android_key_store_string = env.new_string("AndroidKeyStore")?;
+
+            Ok::<_, Error>(
+                GET_INSTANCE
+                    .call(&[JValue::Object(&android_key_store_string)], env)?
+                    .l()?,
+            )
+        })?;
+
+        Ok(UnloadedKeyStore(key_store.wrap()))
     }
 
-    pub fn load(&self, env: &mut AttachGuard<'a>) {
-        env.call_method(
-            self.l(),
-            "load",
-            "(Ljava/security/KeyStore$LoadStoreParameter;)V",
-            &[JValue::Object(&JObject::null())],
-        )
-        .expect("Failed to call load() method");
-    }
-
-    pub fn aliases(&self, env: &mut AttachGuard<'a>) -> Vec<String> {
-        let mut res = vec![];
-        let aliases = env
-            .call_method(self.l(), "aliases", "()Ljava/util/Enumeration;", &[])
-            .expect("Failed to call aliases() method")
-            .l()
-            .expect("Failed to get Enumeration");
-
-        while env
-            .call_method(&aliases, "hasMoreElements", "()Z", &[])
-            .expect("Failed to call hasMoreElements() method")
-            .z()
-            .expect("Failed to get boolean")
-        {
-            let string_object: JString<'_> = env
-                .call_method(&aliases, "nextElement", "()Ljava/lang/Object;", &[])
-                .expect("Failed to call nextElement() method")
-                .l()
-                .expect("Failed to get Object")
-                .into();
-            res.push(
-                env.get_string(&string_object)
-                    .expect("Failed to get string")
-                    .to_str()
-                    .expect("Failed to get str")
-                    .into(),
-            );
+    /// [`Self::get_instance`] followed by [`UnloadedKeyStore::load`]
+    pub fn get_loaded_instance(env: &mut JNIEnv<'a>) -> Result<AndroidKeyStore<'a>> {
+        Self::get_instance(env)?.load(env)
+    }
+
+    /// A loaded instance shared by the whole process, created on first use
+    pub fn get_shared_instance(env: &mut JNIEnv<'a>) -> Result<AndroidKeyStore<'a>> {
+        static SHARED: OnceLock<OwnedAndroidKeyStore> = OnceLock::new();
+
+        if let Some(key_store) = SHARED.get() {
+            return key_store.as_local(env);
         }
 
-        res
+        let key_store = Self::get_loaded_instance(env)?;
+        // Another thread may have won the race, its instance is kept and ours is only used once
+        let _ = SHARED.set(key_store.to_owned(env)?);
+
+        Ok(key_store)
+    }
+
+    pub fn aliases(&self, env: &mut JNIEnv<'a>) -> Result<Vec<String>> {
+        self.iter_aliases(env)?.collect()
+    }
+
+    /// Lazy iterator over the aliases, see [`Aliases`]
+    pub fn iter_aliases<'e>(&self, env: &'e mut JNIEnv<'a>) -> Result<Aliases<'e, 'a>> {
+        static ALIASES: Method = Method::new(&KEY_STORE, "aliases", "()Ljava/util/Enumeration;");
+
+        let enumeration = ALIASES.call(self.l(), &[], env)?.l()?;
+
+        Ok(Aliases::new(enumeration, env))
     }
 
-    pub fn get_entry<S>(&self, alias: S, env: &mut AttachGuard<'a>) -> PrivateKeyEntry<'a>
+    pub fn contains_alias<S>(&self, alias: S, env: &mut JNIEnv<'a>) -> Result<bool>
     where
         S: Into<JNIString>,
     {
-        let alias = env.new_string(alias).unwrap();
+        static CONTAINS_ALIAS: Method =
+            Method::new(&KEY_STORE, "containsAlias", "(Ljava/lang/String;)Z");
 
-        let entry = env.call_method(self.l(), "getEntry", "(Ljava/lang/String;Ljava/security/KeyStore$ProtectionParameter;)Ljava/security/KeyStore$Entry;", &[JValue::Object(&alias), JValue::Object(&JObject::null())])
-        // .expect("Failed to get entry").l().expect("Failed to get JObject")
-        ;
+        self.call_with_alias(&CONTAINS_ALIAS, alias, |res| res.z(), env)
+    }
 
-        if env.exception_check().expect("Failed to check exception") {
-            env.exception_describe()
-                .expect("Failed to describe exception");
-        }
-        let entry = entry
-            .expect("Failed to get entry")
-            .l()
-            .expect("Failed to get JObject");
-
-        if !env
-            .is_instance_of(&entry, "java/security/KeyStore$PrivateKeyEntry")
-            .expect("Failed to call instanceof")
-        {
-            todo!("Handle other entry types")
-        }
-        entry.into()
+    pub fn is_key_entry<S>(&self, alias: S, env: &mut JNIEnv<'a>) -> Result<bool>
+    where
+        S: Into<JNIString>,
+    {
+        static IS_KEY_ENTRY: Method =
+            Method::new(&KEY_STORE, "isKeyEntry", "(Ljava/lang/String;)Z");
+
+        self.call_with_alias(&IS_KEY_ENTRY, alias, |res| res.z(), env)
     }
-}
 
-impl<'a> Object<'a> for AndroidKeyStore<'a> {
-    fn class(env: &mut AttachGuard<'a>) -> JClass<'a> {
-        env.find_class("android/security/keystore/AndroidKeyStore")
-            .expect("Failed to find AndroidKeyStore class")
+    pub fn is_certificate_entry<S>(&self, alias: S, env: &mut JNIEnv<'a>) -> Result<bool>
+    where
+        S: Into<JNIString>,
+    {
+        static IS_CERTIFICATE_ENTRY: Method =
+            Method::new(&KEY_STORE, "isCertificateEntry", "(Ljava/lang/String;)Z");
+
+        self.call_with_alias(&IS_CERTIFICATE_ENTRY, alias, |res| res.z(), env)
     }
 
-    fn l(&self) -> &JObject<'a> {
-        &self.0
+    /// Deletes the entry identified by `alias`, does nothing if there is none
+    pub fn delete_entry<S>(&self, alias: S, env: &mut JNIEnv<'a>) -> Result<()>
+    where
+        S: Into<JNIString>,
+    {
+        static DELETE_ENTRY: Method =
+            Method::new(&KEY_STORE, "deleteEntry", "(Ljava/lang/String;)V");
+
+        self.call_with_alias(&DELETE_ENTRY, alias, |res| res.v(), env)
     }
-}
 
-pub struct PrivateKeyEntry<'a>(JObject<'a>);
+    /// Number of entries in the keystore
+    pub fn size(&self, env: &mut JNIEnv<'a>) -> Result<usize> {
+        static SIZE: Method = Method::new(&KEY_STORE, "size", "()I");
 
-impl<'a> From<JObject<'a>> for PrivateKeyEntry<'a> {
-    fn from(value: JObject<'a>) -> Self {
-        PrivateKeyEntry(value)
+        Ok(SIZE.call(self.l(), &[], env)?.i()? as usize)
     }
-}
 
-impl<'a> PrivateKeyEntry<'a> {
-    pub fn get_private_key(&self, env: &mut AttachGuard<'a>) -> PrivateKey<'a> {
-        env.call_method(
-            self.l(),
-            "getPrivateKey",
-            "()Ljava/security/PrivateKey;",
-            &[],
-        )
-        .expect("Failed to call getPrivateKey() method")
-        .l()
-        .expect("Failed to get JObject")
-        .into()
+    /// Creation date of the entry identified by `alias`, `None` if there is no such entry
+    pub fn get_creation_date<S>(&self, alias: S, env: &mut JNIEnv<'a>) -> Result<Option<SystemTime>>
+    where
+        S: Into<JNIString>,
+    {
+        static GET_CREATION_DATE: Method = Method::new(
+            &KEY_STORE,
+            "getCreationDate",
+            "(Ljava/lang/String;)Ljava/util/Date;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let alias = env.new_string(alias)?;
+            let date = GET_CREATION_DATE
+                .call(self.l(), &[JValue::Object(&alias)], env)?
+                .l()?;
+
+            match date.is_null() {
+                true => Ok(None),
+                false => Ok(Some(date_to_system_time(&date, env)?)),
+            }
+        })
+    }
+
+    /// Calls a `KeyStore` method taking a single alias, `extract` reads its return value
+    fn call_with_alias<S, T, F>(
+        &self,
+        method: &Method,
+        alias: S,
+        extract: F,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<T>
+    where
+        S: Into<JNIString>,
+        F: FnOnce(JValueOwned<'_>) -> jni::errors::Result<T>,
+    {
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let alias = env.new_string(alias)?;
+            let res = method.call(self.l(), &[JValue::Object(&alias)], env)?;
+
+            Ok(extract(res)?)
+        })
+    }
+
+    /// DER encoding of the certificate of the entry identified by `alias`
+    ///
+    /// For a key entry this is the leaf of its chain. `None` if there is no such entry
+    /// or it has no certificate.
+    pub fn get_certificate<S>(&self, alias: S, env: &mut JNIEnv<'a>) -> Result<Option<Vec<u8>>>
+    where
+        S: Into<JNIString>,
+    {
+        static GET_CERTIFICATE: Method = Method::new(
+            &KEY_STORE,
+            "getCertificate",
+            "(Ljava/lang/String;)Ljava/security/cert/Certificate;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let alias = env.new_string(alias)?;
+            let certificate: Certificate = GET_CERTIFICATE
+                .call(self.l(), &[JValue::Object(&alias)], env)?
+                .l()?
+                .wrap();
+
+            match certificate.l().is_null() {
+                true => Ok(None),
+                false => Ok(Some(certificate.get_encoded(env)?)),
+            }
+        })
     }
-}
 
-impl<'a> Object<'a> for PrivateKeyEntry<'a> {
-    fn class(env: &mut AttachGuard<'a>) -> JClass<'a> {
-        env.find_class("java/security/KeyStore$PrivateKeyEntry")
-            .expect("Failed to find PrivateKeyEntry class")
+    /// DER encodings of the certificate chain of the key entry identified by `alias`, leaf first
+    ///
+    /// `None` if there is no such entry or it is not a private key entry
+    pub fn get_certificate_chain<S>(
+        &self,
+        alias: S,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Option<Vec<Vec<u8>>>>
+    where
+        S: Into<JNIString>,
+    {
+        static GET_CERTIFICATE_CHAIN: Method = Method::new(
+            &KEY_STORE,
+            "getCertificateChain",
+            "(Ljava/lang/String;)[Ljava/security/cert/Certificate;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let alias = env.new_string(alias)?;
+            let chain: JObjectArray = GET_CERTIFICATE_CHAIN
+                .call(self.l(), &[JValue::Object(&alias)], env)?
+                .l()?
+                .into();
+
+            match chain.is_null() {
+                true => Ok(None),
+                false => Ok(Some(chain_to_der(&chain, env)?)),
+            }
+        })
     }
 
-    fn l(&self) -> &JObject<'a> {
-        &self.0
+    /// Alias of the first entry whose certificate matches `certificate`, DER or PEM
+    ///
+    /// Trusted certificate entries are matched against their certificate, key entries against
+    /// the leaf of their chain. `None` if no entry matches.
+    pub fn get_certificate_alias(
+        &self,
+        certificate: &[u8],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Option<String>> {
+        static GET_CERTIFICATE_ALIAS: Method = Method::new(
+            &KEY_STORE,
+            "getCertificateAlias",
+            "(Ljava/security/cert/Certificate;)Ljava/lang/String;",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let certificate = Certificate::from_bytes(certificate, env)?;
+            let alias: JString = GET_CERTIFICATE_ALIAS
+                .call(self.l(), &[JValue::Object(certificate.l())], env)?
+                .l()?
+                .into();
+
+            match alias.is_null() {
+                true => Ok(None),
+                false => Ok(Some(env.get_string(&alias)?.into())),
+            }
+        })
+    }
+
+    /// Imports raw AES or HMAC key material under `alias`, replacing any existing entry
+    ///
+    /// Once imported, the key material cannot be read back.
+    pub fn import_secret_key<S>(
+        &self,
+        alias: S,
+        algorithm: SecretKeyAlgorithm,
+        bytes: &[u8],
+        protection: &KeyProtection<'a>,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()>
+    where
+        S: Into<JNIString>,
+    {
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let secret_key = SecretKey::from_bytes(algorithm, bytes, env)?;
+            let entry = SecretKeyEntry::new(&secret_key, env)?;
+
+            self.set_entry(alias, entry.l(), protection.l(), env)
+        })
+    }
+
+    /// Imports a PKCS#8 encoded RSA or EC private key and its DER encoded certificate chain,
+    /// leaf first, under `alias`, replacing any existing entry
+    ///
+    /// The key algorithm is taken from the leaf certificate, which must hold the public half
+    /// of the key.
+    pub fn import_private_key<S, C>(
+        &self,
+        alias: S,
+        pkcs8: &[u8],
+        chain: &[C],
+        protection: &KeyProtection<'a>,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()>
+    where
+        S: Into<JNIString>,
+        C: AsRef<[u8]>,
+    {
+        if chain.is_empty() {
+            return Err(Error::InvalidCertificateChain("empty chain".to_string()));
+        }
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY + chain.len() as i32, |env| {
+            let chain = chain
+                .iter()
+                .map(|der| Certificate::from_bytes(der.as_ref(), env))
+                .collect::<Result<Vec<_>>>()?;
+
+            let public_key = chain[0].get_public_key(env)?;
+            let algorithm = match public_key.get_algorithm(env)?.as_str() {
+                "RSA" => Algorithm::RSA,
+                "EC" => Algorithm::EC,
+                other => return Err(Error::UnsupportedAlgorithm(other.to_string())),
+            };
+
+            let private_key = PrivateKey::from_pkcs8(pkcs8, algorithm, env)?;
+            if !private_key.matches_certificate(&chain[0], algorithm, env)? {
+                return Err(Error::InvalidCertificateChain(
+                    "the leaf certificate does not match the private key".to_string(),
+                ));
+            }
+
+            let entry = PrivateKeyEntry::new(&private_key, &chain, env)?;
+
+            self.set_entry(alias, entry.l(), protection.l(), env)
+        })
+    }
+
+    /// Imports a key wrapped by a server to the [`Purpose::WrapKey`] key stored under
+    /// `wrapping_alias`, so that the key material never appears in the clear on the device
+    ///
+    /// `wrapped` is a DER encoded [`wrapped_key::SecureKeyWrapper`], whose transport key was
+    /// encrypted with RSA-OAEP using `spec`. Requires API level 28.
+    ///
+    /// [`Purpose::WrapKey`]: keygen_parameter_spec::Purpose::WrapKey
+    pub fn import_wrapped_key<S, W>(
+        &self,
+        alias: S,
+        wrapped: &[u8],
+        wrapping_alias: W,
+        spec: &OaepSpec,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()>
+    where
+        S: Into<JNIString>,
+        W: Into<JNIString>,
+    {
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let entry = WrappedKeyEntry::new(wrapped, wrapping_alias, spec, env)?;
+
+            self.set_entry(alias, entry.l(), &JObject::null(), env)
+        })
+    }
+
+    /// Stores a trusted certificate, DER encoded or PEM armored, under `alias`
+    ///
+    /// Replaces an existing trusted certificate entry, fails if `alias` holds a key.
+    /// Malformed certificates fail with [`Error::InvalidCertificate`].
+    pub fn set_certificate_entry<S>(
+        &self,
+        alias: S,
+        certificate: &[u8],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()>
+    where
+        S: Into<JNIString>,
+    {
+        static SET_CERTIFICATE_ENTRY: Method = Method::new(
+            &KEY_STORE,
+            "setCertificateEntry",
+            "(Ljava/lang/String;Ljava/security/cert/Certificate;)V",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let certificate = Certificate::from_bytes(certificate, env)?;
+            let alias = env.new_string(alias)?;
+            SET_CERTIFICATE_ENTRY.call(
+                self.l(),
+                &[JValue::Object(&alias), JValue::Object(certificate.l())],
+                env,
+            )?;
+
+            Ok(())
+        })
+    }
+
+    /// Calls `KeyStore.setEntry`, `protection` may be null
+    fn set_entry<S>(
+        &self,
+        alias: S,
+        entry: &JObject<'_>,
+        protection: &JObject<'_>,
+        env: &mut JNIEnv<'_>,
+    ) -> Result<()>
+    where
+        S: Into<JNIString>,
+    {
+        static SET_ENTRY: Method = Method::new(
+            &KEY_STORE,
+            "setEntry",
+            "(Ljava/lang/String;Ljava/security/KeyStore$Entry;Ljava/security/KeyStore$ProtectionParameter;)V",
+        );
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let alias = env.new_string(alias)?;
+            SET_ENTRY.call(
+                self.l(),
+                &[
+                    JValue::Object(&alias),
+                    JValue::Object(entry),
+                    JValue::Object(protection),
+                ],
+                env,
+            )?;
+
+            Ok(())
+        })
+    }
+
+    /// The entry identified by `alias`, `None` if there is no such entry
+    pub fn get_entry<S>(&self, alias: S, env: &mut JNIEnv<'a>) -> Result<Option<Entry<'a>>>
+    where
+        S: Into<JNIString>,
+    {
+        static GET_ENTRY: Method = Method::new(
+            &KEY_STORE,
+            "getEntry",
+            "(Ljava/lang/String;Ljava/security/KeyStore$ProtectionParameter;)Ljava/security/KeyStore$Entry;",
+        );
+
+        let entry = env.with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+            let alias = env.new_string(alias)?;
+
+            Ok::<_, Error>(
+                GET_ENTRY
+                    .call(
+                        self.l(),
+                        &[JValue::Object(&alias), JValue::Object(&JObject::null())],
+                        env,
+                    )?
+                    .l()?,
+            )
+        })?;
+
+        if entry.is_null() {
+            return Ok(None);
+        }
+
+        Entry::try_from_object(entry, env).map(Some)
     }
 }
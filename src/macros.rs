@@ -0,0 +1,104 @@
+/// Declares an owned, `Send + Sync` variant of a borrowed wrapper, backed by a `GlobalRef`
+///
+/// The owned variant can be stored for the whole life of the process and used from any thread,
+/// `as_local()` turns it back into the borrowed wrapper for the current `JNIEnv`.
+macro_rules! owned {
+    ($(#[$meta:meta])* $owned:ident => $borrowed:ident) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        pub struct $owned(jni::objects::GlobalRef);
+
+        const _: () = {
+            fn assert_send_sync<T: Send + Sync>() {}
+            let _ = assert_send_sync::<$owned>;
+        };
+
+        impl $owned {
+            /// A new local reference to the object, valid for the lifetime of `env`
+            pub fn as_local<'a>(
+                &self,
+                env: &mut jni::JNIEnv<'a>,
+            ) -> $crate::Result<$borrowed<'a>> {
+                Ok($crate::sealed::FromRaw::from_raw(env.new_local_ref(&self.0)?))
+            }
+        }
+
+        impl<'a> $borrowed<'a> {
+            /// A new global reference to the object
+            pub fn to_owned(&self, env: &mut jni::JNIEnv<'a>) -> $crate::Result<$owned> {
+                Ok($owned(env.new_global_ref($crate::Object::l(self))?))
+            }
+        }
+    };
+}
+
+/// Declares a wrapper around a JObject that is an instance of the Java class `$path`
+///
+/// Generates the wrapper struct, its [`crate::Object`] and [`crate::Cast`] impls, a cached
+/// [`crate::cache::Class`] static named `$class` for the methods to use, and optionally an
+/// owned variant through [`owned!`].
+///
+/// The only public constructor is the checked [`crate::Cast::try_cast`], the crate itself wraps
+/// the objects returned by Java methods declared to return `$path` with `from_raw`.
+macro_rules! java_class {
+    (
+        @wrapper
+        $(#[$meta:meta])*
+        $name:ident($class_vis:vis $class:ident = $path:literal) $(, owned $owned:ident)?
+    ) => {
+        $class_vis static $class: $crate::cache::Class = $crate::cache::Class::new($path);
+
+        $(#[$meta])*
+        #[derive(Debug)]
+        pub struct $name<'a>(jni::objects::JObject<'a>);
+
+        impl<'a> $crate::sealed::FromRaw<'a> for $name<'a> {
+            fn from_raw(obj: jni::objects::JObject<'a>) -> Self {
+                Self(obj)
+            }
+        }
+
+        impl<'a> $crate::Object<'a> for $name<'a> {
+            const CLASS_NAME: &'static str = $path;
+
+            fn class(env: &mut jni::JNIEnv<'a>) -> $crate::Result<jni::objects::JClass<'a>> {
+                $class.local(env)
+            }
+
+            fn l(&self) -> &jni::objects::JObject<'a> {
+                &self.0
+            }
+
+            fn into_inner(self) -> jni::objects::JObject<'a> {
+                self.0
+            }
+        }
+
+        $(
+            owned! {
+                #[doc = concat!("A [`", stringify!($name), "`] that is not tied to a `JNIEnv`")]
+                $owned => $name
+            }
+        )?
+    };
+    // `sealed` wrappers carry an invariant the class alone does not prove, e.g. a loaded
+    // keystore, so they get no public `Cast` and are only built by the crate
+    (
+        $(#[$meta:meta])*
+        sealed $name:ident($class_vis:vis $class:ident = $path:literal) $(, owned $owned:ident)?
+    ) => {
+        java_class! {
+            @wrapper $(#[$meta])* $name($class_vis $class = $path) $(, owned $owned)?
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $name:ident($class_vis:vis $class:ident = $path:literal) $(, owned $owned:ident)?
+    ) => {
+        java_class! {
+            @wrapper $(#[$meta])* $name($class_vis $class = $path) $(, owned $owned)?
+        }
+
+        impl<'a> $crate::Cast<'a> for $name<'a> {}
+    };
+}
@@ -0,0 +1,208 @@
+//! A view of the keystore restricted to the aliases of one namespace
+//!
+//! Libraries sharing the app's single AndroidKeyStore each use their own namespace, so their
+//! aliases cannot collide: the alias `session` of the namespace `sdk` is stored as `sdk/session`.
+
+use std::time::SystemTime;
+
+use jni::JNIEnv;
+
+use crate::{
+    AndroidKeyStore, Certificate, Entry, Error, LOCAL_FRAME_CAPACITY, Result, SecretKeyAlgorithm,
+    key_protection::KeyProtection, wrapped_key::OaepSpec,
+};
+
+/// Separates the namespace from the alias in the full keystore alias
+pub const SEPARATOR: char = '/';
+
+/// An [`AndroidKeyStore`] whose aliases are all prefixed with a namespace
+///
+/// Aliases passed to and returned by the methods are relative to the namespace. They may not
+/// contain [`SEPARATOR`], so that an alias can never reach into another namespace.
+#[derive(Debug)]
+pub struct NamespacedKeyStore<'a> {
+    key_store: AndroidKeyStore<'a>,
+    prefix: String,
+}
+
+impl<'a> NamespacedKeyStore<'a> {
+    pub fn new(key_store: AndroidKeyStore<'a>, namespace: &str) -> Result<Self> {
+        if namespace.is_empty() || namespace.contains(SEPARATOR) {
+            return Err(Error::InvalidAlias(namespace.to_string()));
+        }
+
+        Ok(Self {
+            key_store,
+            prefix: format!("{namespace}{SEPARATOR}"),
+        })
+    }
+
+    /// The underlying keystore, which is not restricted to the namespace
+    pub fn key_store(&self) -> &AndroidKeyStore<'a> {
+        &self.key_store
+    }
+
+    /// The full keystore alias of `alias`, e.g. to generate a key with a
+    /// [`crate::keygen_parameter_spec::Builder`]
+    pub fn alias(&self, alias: &str) -> Result<String> {
+        if alias.is_empty() || alias.contains(SEPARATOR) {
+            return Err(Error::InvalidAlias(alias.to_string()));
+        }
+
+        Ok(format!("{}{alias}", self.prefix))
+    }
+
+    /// `alias` relative to the namespace, `None` if it belongs to another namespace
+    fn strip(&self, alias: &str) -> Option<String> {
+        alias.strip_prefix(&self.prefix).map(str::to_string)
+    }
+
+    /// The aliases of the namespace
+    pub fn aliases(&self, env: &mut JNIEnv<'a>) -> Result<Vec<String>> {
+        self.key_store
+            .iter_aliases(env)?
+            .with_prefix(&self.prefix)
+            .map(|alias| Ok(alias?[self.prefix.len()..].to_string()))
+            .collect()
+    }
+
+    pub fn contains_alias(&self, alias: &str, env: &mut JNIEnv<'a>) -> Result<bool> {
+        self.key_store.contains_alias(self.alias(alias)?, env)
+    }
+
+    pub fn is_key_entry(&self, alias: &str, env: &mut JNIEnv<'a>) -> Result<bool> {
+        self.key_store.is_key_entry(self.alias(alias)?, env)
+    }
+
+    pub fn is_certificate_entry(&self, alias: &str, env: &mut JNIEnv<'a>) -> Result<bool> {
+        self.key_store.is_certificate_entry(self.alias(alias)?, env)
+    }
+
+    pub fn delete_entry(&self, alias: &str, env: &mut JNIEnv<'a>) -> Result<()> {
+        self.key_store.delete_entry(self.alias(alias)?, env)
+    }
+
+    /// Deletes every entry of the namespace, leaving other namespaces untouched
+    pub fn clear(&self, env: &mut JNIEnv<'a>) -> Result<()> {
+        // Collected first, as deleting while enumerating is not supported by every provider
+        let aliases: Vec<String> = self
+            .key_store
+            .iter_aliases(env)?
+            .with_prefix(&self.prefix)
+            .collect::<Result<_>>()?;
+
+        for alias in aliases {
+            self.key_store.delete_entry(alias, env)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_creation_date(
+        &self,
+        alias: &str,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Option<SystemTime>> {
+        self.key_store.get_creation_date(self.alias(alias)?, env)
+    }
+
+    pub fn get_entry(&self, alias: &str, env: &mut JNIEnv<'a>) -> Result<Option<Entry<'a>>> {
+        self.key_store.get_entry(self.alias(alias)?, env)
+    }
+
+    pub fn get_certificate(&self, alias: &str, env: &mut JNIEnv<'a>) -> Result<Option<Vec<u8>>> {
+        self.key_store.get_certificate(self.alias(alias)?, env)
+    }
+
+    pub fn get_certificate_chain(
+        &self,
+        alias: &str,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Option<Vec<Vec<u8>>>> {
+        self.key_store
+            .get_certificate_chain(self.alias(alias)?, env)
+    }
+
+    /// Alias of the first entry of the namespace whose certificate matches `certificate`, DER or
+    /// PEM
+    ///
+    /// Entries of other namespaces are ignored.
+    pub fn get_certificate_alias(
+        &self,
+        certificate: &[u8],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Option<String>> {
+        let certificate = env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            Certificate::from_bytes(certificate, env)?.get_encoded(env)
+        })?;
+
+        let aliases: Vec<String> = self
+            .key_store
+            .iter_aliases(env)?
+            .with_prefix(&self.prefix)
+            .collect::<Result<_>>()?;
+
+        for alias in aliases {
+            if self.key_store.get_certificate(&alias, env)?.as_deref() == Some(&certificate[..]) {
+                return Ok(self.strip(&alias));
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn set_certificate_entry(
+        &self,
+        alias: &str,
+        certificate: &[u8],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()> {
+        self.key_store
+            .set_certificate_entry(self.alias(alias)?, certificate, env)
+    }
+
+    pub fn import_secret_key(
+        &self,
+        alias: &str,
+        algorithm: SecretKeyAlgorithm,
+        bytes: &[u8],
+        protection: &KeyProtection<'a>,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()> {
+        self.key_store
+            .import_secret_key(self.alias(alias)?, algorithm, bytes, protection, env)
+    }
+
+    pub fn import_private_key<C>(
+        &self,
+        alias: &str,
+        pkcs8: &[u8],
+        chain: &[C],
+        protection: &KeyProtection<'a>,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()>
+    where
+        C: AsRef<[u8]>,
+    {
+        self.key_store
+            .import_private_key(self.alias(alias)?, pkcs8, chain, protection, env)
+    }
+
+    /// Imports a wrapped key, `wrapping_alias` must belong to the namespace as well
+    pub fn import_wrapped_key(
+        &self,
+        alias: &str,
+        wrapped: &[u8],
+        wrapping_alias: &str,
+        spec: &OaepSpec,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<()> {
+        self.key_store.import_wrapped_key(
+            self.alias(alias)?,
+            wrapped,
+            self.alias(wrapping_alias)?,
+            spec,
+            env,
+        )
+    }
+}
@@ -0,0 +1,76 @@
+use jni::{
+    JNIEnv,
+    objects::{JString, JValue},
+    strings::JNIString,
+};
+
+use crate::{
+    LOCAL_FRAME_CAPACITY, Object, Result,
+    cache::{Class, Constructor, Method},
+    sealed::Wrap,
+};
+
+static SECRET_KEY_SPEC: Class = Class::new("javax/crypto/spec/SecretKeySpec");
+
+/// Algorithms of the secret keys the Android KeyStore can hold
+#[derive(Debug, Clone, Copy)]
+pub enum SecretKeyAlgorithm {
+    Aes,
+    HmacSha1,
+    HmacSha224,
+    HmacSha256,
+    HmacSha384,
+    HmacSha512,
+}
+
+impl From<&SecretKeyAlgorithm> for JNIString {
+    fn from(val: &SecretKeyAlgorithm) -> Self {
+        match val {
+            SecretKeyAlgorithm::Aes => "AES".into(),
+            SecretKeyAlgorithm::HmacSha1 => "HmacSHA1".into(),
+            SecretKeyAlgorithm::HmacSha224 => "HmacSHA224".into(),
+            SecretKeyAlgorithm::HmacSha256 => "HmacSHA256".into(),
+            SecretKeyAlgorithm::HmacSha384 => "HmacSHA384".into(),
+            SecretKeyAlgorithm::HmacSha512 => "HmacSHA512".into(),
+        }
+    }
+}
+
+java_class! {
+    /// A wrapper around a JObject representing a `javax.crypto.SecretKey`
+    SecretKey(SECRET_KEY = "javax/crypto/SecretKey"), owned OwnedSecretKey
+}
+
+impl<'a> SecretKey<'a> {
+    /// A `SecretKeySpec` holding the raw key material, outside of any keystore
+    pub fn from_bytes(
+        algorithm: SecretKeyAlgorithm,
+        bytes: &[u8],
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self> {
+        static NEW_SECRET_KEY_SPEC: Constructor =
+            Constructor::new(&SECRET_KEY_SPEC, "([BLjava/lang/String;)V");
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                let bytes = env.byte_array_from_slice(bytes)?;
+                let algorithm = env.new_string(&algorithm)?;
+
+                NEW_SECRET_KEY_SPEC
+                    .new_object(&[JValue::Object(&bytes), JValue::Object(&algorithm)], env)
+            })?
+            .wrap())
+    }
+
+    /// The key algorithm, e.g. `AES` or `HmacSHA256`
+    pub fn get_algorithm(&self, env: &mut JNIEnv<'a>) -> Result<String> {
+        static GET_ALGORITHM: Method =
+            Method::new(&SECRET_KEY, "getAlgorithm", "()Ljava/lang/String;");
+
+        env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+            let algorithm: JString = GET_ALGORITHM.call(self.l(), &[], env)?.l()?.into();
+
+            Ok(env.get_string(&algorithm)?.into())
+        })
+    }
+}
@@ -1,72 +1,220 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, SystemTime},
+};
+
 use jni::{
-    AttachGuard, JavaVM,
-    objects::{JObjectArray, JString},
+    JNIEnv, JavaVM,
+    objects::{GlobalRef, JObjectArray, JString, JValue},
     strings::JNIString,
-    sys::_jobject,
 };
 
-use crate::JObject;
+use crate::{
+    Cast, Error, JObject, Object, Result,
+    cache::{Class, Constructor, Method},
+    context::{CONTEXT, Context},
+    sealed::Wrap,
+};
+
+static STRING: Class = Class::new("java/lang/String");
+static FILE: Class = Class::new("java/io/File");
+static CLASS: Class = Class::new("java/lang/Class");
+static DATE: Class = Class::new("java/util/Date");
+
+/// Number of local references reserved by the frame each wrapper operation runs in
+pub(crate) const LOCAL_FRAME_CAPACITY: i32 = 16;
+
+struct Runtime {
+    vm: JavaVM,
+    context: Option<GlobalRef>,
+}
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// Sets the JVM and the `android.content.Context` used by [`with_jni_env`]
+///
+/// Must be called once, before `with_jni_env`, by apps where `ndk_context` is not initialised:
+/// Flutter, React Native and plain JNI libraries, typically from `JNI_OnLoad` or a native
+/// method. `context` must be an `android.content.Context`, otherwise this fails with
+/// [`Error::ClassCast`]. It can be `None` where there is no app context, e.g. a desktop JVM in
+/// tests, `with_jni_env` then hands `None` to its closure.
+pub fn init(vm: JavaVM, context: Option<GlobalRef>) -> Result<()> {
+    if let Some(context) = &context {
+        let mut env = vm.attach_current_thread()?;
+        if !Context::is_instance(context.as_obj(), &mut env)? {
+            return Err(Error::ClassCast {
+                expected: Context::CLASS_NAME,
+                actual: class_name(context.as_obj(), &mut env)?,
+            });
+        }
+    }
+
+    RUNTIME
+        .set(Runtime { vm, context })
+        .map_err(|_| Error::AlreadyInitialized)
+}
 
-pub fn with_jni_env<Func, Ret>(f: Func) -> Ret
+/// Attaches the current thread to the JVM and runs `f` with its `JNIEnv` and the app `Context`
+///
+/// Uses the JVM given to [`init`], or falls back to `ndk_context` in native activities. The
+/// fallback is only available with `panic = "unwind"`; builds with `panic = "abort"` must call
+/// [`init`] first and get [`Error::NotInitialized`] otherwise. The context is `None` only when
+/// `init` was given none.
+pub fn with_jni_env<Func, Ret>(f: Func) -> Result<Ret>
 where
-    Func: FnOnce(AttachGuard<'_>, *mut _jobject) -> Ret,
+    Func: for<'local> FnOnce(&mut JNIEnv<'local>, Option<Context<'local>>) -> Result<Ret>,
 {
-    let ctx = ndk_context::android_context();
+    if let Some(runtime) = RUNTIME.get() {
+        let mut env = runtime.vm.attach_current_thread()?;
+        let context = match &runtime.context {
+            // Checked by init()
+            Some(context) => Some(env.new_local_ref(context)?.wrap()),
+            None => None,
+        };
+
+        return f(&mut env, context);
+    }
+
+    let ctx = ndk_android_context()?;
 
     // SAFETY: We assume that the pointer returned by android_context() is valid
-    let vm = unsafe { JavaVM::from_raw(ctx.vm().cast()).expect("Failed to get JavaVM") };
-    let env = vm.attach_current_thread().expect("Failed to attach thread");
+    let vm = unsafe { JavaVM::from_raw(ctx.vm().cast())? };
+    let mut env = vm.attach_current_thread()?;
 
-    // SAFETY: We assume that the activity pointer is valid
+    // SAFETY: We assume that the context pointer is valid
     let activity = unsafe { JObject::from_raw(ctx.context().cast()) };
+    let context = env.new_local_ref(activity)?;
+    let context = Context::try_cast(context, &mut env)?;
 
-    f(env, activity.to_owned())
+    f(&mut env, Some(context))
 }
 
-pub fn make_string_array<'a, S>(strings: &'a [S], env: &mut AttachGuard<'a>) -> JObjectArray<'a>
+/// `ndk_context::android_context()` panics when the context was never initialised, which can
+/// only be caught when panics unwind. The default panic hook still reports that panic.
+#[cfg(panic = "unwind")]
+fn ndk_android_context() -> Result<ndk_context::AndroidContext> {
+    std::panic::catch_unwind(ndk_context::android_context).map_err(|_| Error::NotInitialized)
+}
+
+/// With `panic = "abort"` the `ndk_context` fallback would abort the process, so [`init`] is
+/// required.
+#[cfg(not(panic = "unwind"))]
+fn ndk_android_context() -> Result<ndk_context::AndroidContext> {
+    Err(Error::NotInitialized)
+}
+
+pub fn make_string_array<'a, S>(strings: &[S], env: &mut JNIEnv<'a>) -> Result<JObjectArray<'a>>
 where
-    &'a S: Into<JNIString>,
+    for<'s> &'s S: Into<JNIString>,
 {
-    let string_class = env
-        .find_class("java/lang/String")
-        .expect("Cannot find String class");
+    let string_class = STRING.get(env)?;
 
-    let string_array = env
-        .new_object_array(strings.len() as i32, &string_class, JObject::null())
-        .expect("Cannot create string array object");
+    let string_array = env.new_object_array(strings.len() as i32, string_class, JObject::null())?;
 
     for (i, val) in strings.iter().enumerate() {
-        let java_str: JString = env.new_string(val).expect("Cannot create string object");
-        env.set_object_array_element(&string_array, i as i32, java_str)
-            .expect("Failed to add element to string array");
+        let java_str: JString = env.new_string(val)?;
+        env.set_object_array_element(&string_array, i as i32, &java_str)?;
+        env.delete_local_ref(java_str)?;
     }
 
-    string_array
+    Ok(string_array)
+}
+
+/// Calls a `String...` setter of a builder in a local frame
+pub(crate) fn call_with_strings<S>(
+    builder: &JObject<'_>,
+    method: &Method,
+    values: &[S],
+    env: &mut JNIEnv<'_>,
+) -> Result<()>
+where
+    for<'s> &'s S: Into<JNIString>,
+{
+    env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+        let string_array = make_string_array(values, env)?;
+
+        method.call(builder, &[JValue::Object(&string_array)], env)?;
+
+        Ok(())
+    })
+}
+
+/// Calls a `Date` setter of a builder in a local frame
+pub(crate) fn call_with_date(
+    builder: &JObject<'_>,
+    method: &Method,
+    time: SystemTime,
+    env: &mut JNIEnv<'_>,
+) -> Result<()> {
+    env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+        let date = system_time_to_date(time, env)?;
+
+        method.call(builder, &[JValue::Object(&date)], env)?;
+
+        Ok(())
+    })
 }
 
 pub fn get_internal_directory_path<'a>(
-    env: &mut AttachGuard<'a>,
-    activity: &JObject<'a>,
-) -> String {
-    let files_dir_obj = env
-        .call_method(activity, "getFilesDir", "()Ljava/io/File;", &[])
-        .expect("Failed to call getFilesDir() method")
-        .l()
-        .expect("Failed to get JObject");
-
-    let path_obj: JString<'a> = env
-        .call_method(
-            files_dir_obj,
-            "getAbsolutePath",
-            "()Ljava/lang/String;",
-            &[],
-        )
-        .expect("Failed to call getAbsolutePath() method")
-        .l()
-        .expect("Failed to get JObject")
-        .into();
-
-    env.get_string(&path_obj)
-        .expect("Failed to get string")
-        .into()
+    env: &mut JNIEnv<'a>,
+    context: &Context<'a>,
+) -> Result<String> {
+    static GET_FILES_DIR: Method = Method::new(&CONTEXT, "getFilesDir", "()Ljava/io/File;");
+    static GET_ABSOLUTE_PATH: Method =
+        Method::new(&FILE, "getAbsolutePath", "()Ljava/lang/String;");
+
+    env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+        let files_dir_obj = GET_FILES_DIR.call(context.l(), &[], env)?.l()?;
+
+        let path_obj: JString = GET_ABSOLUTE_PATH
+            .call(&files_dir_obj, &[], env)?
+            .l()?
+            .into();
+
+        Ok(env.get_string(&path_obj)?.into())
+    })
+}
+
+/// Fully qualified name of the class of `obj`, or `"null"`
+pub(crate) fn class_name(obj: &JObject<'_>, env: &mut JNIEnv<'_>) -> Result<String> {
+    static GET_NAME: Method = Method::new(&CLASS, "getName", "()Ljava/lang/String;");
+
+    if obj.is_null() {
+        return Ok("null".into());
+    }
+
+    env.with_local_frame(LOCAL_FRAME_CAPACITY, |env| {
+        let class = env.get_object_class(obj)?;
+        let name: JString = GET_NAME.call(&class, &[], env)?.l()?.into();
+
+        Ok(env.get_string(&name)?.into())
+    })
+}
+
+/// Converts a `java.util.Date` to a `SystemTime`
+pub(crate) fn date_to_system_time(date: &JObject<'_>, env: &mut JNIEnv<'_>) -> Result<SystemTime> {
+    static GET_TIME: Method = Method::new(&DATE, "getTime", "()J");
+
+    let millis = GET_TIME.call(date, &[], env)?.j()?;
+    let offset = Duration::from_millis(millis.unsigned_abs());
+
+    Ok(match millis >= 0 {
+        true => SystemTime::UNIX_EPOCH + offset,
+        false => SystemTime::UNIX_EPOCH - offset,
+    })
+}
+
+/// Converts a `SystemTime` to a new `java.util.Date`, truncated to the millisecond
+pub(crate) fn system_time_to_date<'a>(
+    time: SystemTime,
+    env: &mut JNIEnv<'a>,
+) -> Result<JObject<'a>> {
+    static NEW_DATE: Constructor = Constructor::new(&DATE, "(J)V");
+
+    let millis = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(after) => after.as_millis() as i64,
+        Err(before) => -(before.duration().as_millis() as i64),
+    };
+
+    NEW_DATE.new_object(&[JValue::Long(millis)], env)
 }
@@ -0,0 +1,482 @@
+//! Secure key import: keys wrapped by a server to a device key with [`Purpose::WrapKey`]
+//!
+//! The server encrypts the key to import with an ephemeral AES-GCM transport key, encrypts the
+//! transport key with the public half of the wrapping key and packs everything, along with the
+//! authorizations the imported key gets, in a DER encoded `SecureKeyWrapper`:
+//!
+//! ```text
+//! KeyDescription ::= SEQUENCE {
+//!     keyFormat INTEGER,
+//!     keyParams AuthorizationList,
+//! }
+//!
+//! SecureKeyWrapper ::= SEQUENCE {
+//!     version INTEGER,
+//!     encryptedTransportKey OCTET STRING,
+//!     initializationVector OCTET STRING,
+//!     keyDescription KeyDescription,
+//!     encryptedKey OCTET STRING,
+//!     tag OCTET STRING,
+//! }
+//! ```
+//!
+//! The encoder is plain Rust, so it can run on the server as well as on the device.
+
+use jni::{
+    JNIEnv,
+    objects::{JObject, JValue},
+    strings::JNIString,
+};
+
+use crate::{
+    LOCAL_FRAME_CAPACITY, Result, api_level,
+    cache::{Class, Constructor},
+    der,
+    exception::Catch,
+    keygen_parameter_spec::{
+        AuthType, BlockMode, Digest, EcCurve, Padding, Purpose, SignaturePadding,
+    },
+    sealed::Wrap,
+};
+
+static OAEP_PARAMETER_SPEC: Class = Class::new("javax/crypto/spec/OAEPParameterSpec");
+static MGF1_PARAMETER_SPEC: Class = Class::new("java/security/spec/MGF1ParameterSpec");
+static PSOURCE_PSPECIFIED: Class = Class::new("javax/crypto/spec/PSource$PSpecified");
+
+/// `SecureKeyWrapper` version understood by Keymaster and KeyMint
+const VERSION: i64 = 0;
+
+/// Tag numbers of the `AuthorizationList` fields, Keymaster tags without their type bits
+mod tag {
+    pub const PURPOSE: u32 = 1;
+    pub const ALGORITHM: u32 = 2;
+    pub const KEY_SIZE: u32 = 3;
+    pub const BLOCK_MODE: u32 = 4;
+    pub const DIGEST: u32 = 5;
+    pub const PADDING: u32 = 6;
+    pub const CALLER_NONCE: u32 = 7;
+    pub const MIN_MAC_LENGTH: u32 = 8;
+    pub const EC_CURVE: u32 = 10;
+    pub const RSA_PUBLIC_EXPONENT: u32 = 200;
+    pub const NO_AUTH_REQUIRED: u32 = 503;
+    pub const USER_AUTH_TYPE: u32 = 504;
+    pub const AUTH_TIMEOUT: u32 = 505;
+}
+
+/// Encoding of the wrapped key material, Keymaster `KeyFormat`
+#[repr(i64)]
+#[derive(Debug, Clone, Copy)]
+pub enum KeyFormat {
+    /// PKCS#8 `PrivateKeyInfo`, for RSA and EC keys
+    Pkcs8 = 1,
+    /// Raw bytes, for AES and HMAC keys
+    Raw = 3,
+}
+
+/// Keymaster `Algorithm`
+#[repr(i64)]
+#[derive(Debug, Clone, Copy)]
+pub enum KeyAlgorithm {
+    Rsa = 1,
+    Ec = 3,
+    Aes = 32,
+    TripleDes = 33,
+    Hmac = 128,
+}
+
+/// Keymaster values of the crate's `KeyProperties` based enums
+trait Keymaster {
+    fn keymaster(&self) -> i64;
+}
+
+impl Keymaster for Purpose {
+    fn keymaster(&self) -> i64 {
+        match self {
+            Purpose::Encrypt => 0,
+            Purpose::Decrypt => 1,
+            Purpose::Sign => 2,
+            Purpose::Verify => 3,
+            Purpose::WrapKey => 5,
+            Purpose::AgreeKey => 6,
+            Purpose::AttestKey => 7,
+        }
+    }
+}
+
+impl Keymaster for BlockMode {
+    fn keymaster(&self) -> i64 {
+        match self {
+            BlockMode::Ecb => 1,
+            BlockMode::Cbc => 2,
+            BlockMode::Ctr => 3,
+            BlockMode::Gcm => 32,
+        }
+    }
+}
+
+impl Keymaster for Digest {
+    fn keymaster(&self) -> i64 {
+        match self {
+            Digest::None => 0,
+            Digest::Md5 => 1,
+            Digest::Sha1 => 2,
+            Digest::Sha224 => 3,
+            Digest::Sha256 => 4,
+            Digest::Sha384 => 5,
+            Digest::Sha512 => 6,
+        }
+    }
+}
+
+impl Keymaster for Padding {
+    fn keymaster(&self) -> i64 {
+        match self {
+            Padding::None => 1,
+            Padding::RsaOaep => 2,
+            Padding::RsaPkcs1 => 4,
+            Padding::Pkcs7 => 64,
+        }
+    }
+}
+
+impl Keymaster for SignaturePadding {
+    fn keymaster(&self) -> i64 {
+        match self {
+            SignaturePadding::Pss => 3,
+            SignaturePadding::Pkcs1 => 5,
+        }
+    }
+}
+
+impl Keymaster for EcCurve {
+    fn keymaster(&self) -> i64 {
+        match self {
+            EcCurve::P256 => 1,
+            EcCurve::P384 => 2,
+            EcCurve::P521 => 3,
+        }
+    }
+}
+
+/// The authorizations of the imported key, encoded as a Keymaster `AuthorizationList`
+///
+/// Fields left empty are omitted from the encoding.
+#[derive(Default)]
+pub struct AuthorizationList {
+    pub purposes: Vec<Purpose>,
+    pub algorithm: Option<KeyAlgorithm>,
+    pub key_size: Option<u32>,
+    pub block_modes: Vec<BlockMode>,
+    pub digests: Vec<Digest>,
+    pub paddings: Vec<Padding>,
+    /// Encoded in the same `padding` set as `paddings`
+    pub signature_paddings: Vec<SignaturePadding>,
+    pub caller_nonce: bool,
+    pub min_mac_length: Option<u32>,
+    pub ec_curve: Option<EcCurve>,
+    pub rsa_public_exponent: Option<u64>,
+    /// Must be set unless `user_auth_types` is
+    pub no_auth_required: bool,
+    pub user_auth_types: Vec<AuthType>,
+    /// Seconds the key stays usable after the user authenticated
+    pub auth_timeout: Option<u32>,
+}
+
+impl AuthorizationList {
+    /// DER encoding, fields in ascending tag order as Keymaster expects
+    pub fn to_der(&self) -> Vec<u8> {
+        fn set_of<T: Keymaster>(number: u32, values: &[T], out: &mut Vec<Vec<u8>>) {
+            if !values.is_empty() {
+                let values = values.iter().map(|v| der::integer(v.keymaster())).collect();
+                out.push(der::explicit(number, &der::set_of(values)));
+            }
+        }
+        fn integer(number: u32, value: Option<i64>, out: &mut Vec<Vec<u8>>) {
+            if let Some(value) = value {
+                out.push(der::explicit(number, &der::integer(value)));
+            }
+        }
+        fn null(number: u32, present: bool, out: &mut Vec<Vec<u8>>) {
+            if present {
+                out.push(der::explicit(number, &der::null()));
+            }
+        }
+
+        let mut fields = vec![];
+        set_of(tag::PURPOSE, &self.purposes, &mut fields);
+        integer(
+            tag::ALGORITHM,
+            self.algorithm.map(|a| a as i64),
+            &mut fields,
+        );
+        integer(tag::KEY_SIZE, self.key_size.map(i64::from), &mut fields);
+        set_of(tag::BLOCK_MODE, &self.block_modes, &mut fields);
+        set_of(tag::DIGEST, &self.digests, &mut fields);
+        if !self.paddings.is_empty() || !self.signature_paddings.is_empty() {
+            let paddings = self.paddings.iter().map(Keymaster::keymaster);
+            let signature_paddings = self.signature_paddings.iter().map(Keymaster::keymaster);
+            let values = paddings
+                .chain(signature_paddings)
+                .map(der::integer)
+                .collect();
+            fields.push(der::explicit(tag::PADDING, &der::set_of(values)));
+        }
+        null(tag::CALLER_NONCE, self.caller_nonce, &mut fields);
+        integer(
+            tag::MIN_MAC_LENGTH,
+            self.min_mac_length.map(i64::from),
+            &mut fields,
+        );
+        integer(
+            tag::EC_CURVE,
+            self.ec_curve.map(|c| c.keymaster()),
+            &mut fields,
+        );
+        if let Some(exponent) = self.rsa_public_exponent {
+            fields.push(der::explicit(
+                tag::RSA_PUBLIC_EXPONENT,
+                &der::unsigned_integer(exponent),
+            ));
+        }
+        null(tag::NO_AUTH_REQUIRED, self.no_auth_required, &mut fields);
+        if !self.user_auth_types.is_empty() {
+            let types = self
+                .user_auth_types
+                .iter()
+                .fold(0, |acc, t| acc | *t as i64);
+            integer(tag::USER_AUTH_TYPE, Some(types), &mut fields);
+        }
+        integer(
+            tag::AUTH_TIMEOUT,
+            self.auth_timeout.map(i64::from),
+            &mut fields,
+        );
+
+        der::sequence(&fields)
+    }
+}
+
+/// A key wrapped for secure import, see the module documentation
+pub struct SecureKeyWrapper {
+    /// The AES-256 transport key, encrypted with RSA-OAEP to the wrapping key
+    pub encrypted_transport_key: Vec<u8>,
+    /// The 12 byte AES-GCM nonce used to encrypt the key
+    pub initialization_vector: Vec<u8>,
+    pub key_format: KeyFormat,
+    pub authorization_list: AuthorizationList,
+    /// The key material, encrypted with AES-GCM under the transport key, without the tag
+    pub encrypted_key: Vec<u8>,
+    /// The 16 byte AES-GCM tag, whose additional data is the DER encoded `KeyDescription`
+    pub tag: Vec<u8>,
+}
+
+impl SecureKeyWrapper {
+    /// DER encoded `KeyDescription`, the additional authenticated data of the AES-GCM encryption
+    pub fn key_description(
+        key_format: KeyFormat,
+        authorization_list: &AuthorizationList,
+    ) -> Vec<u8> {
+        der::sequence(&[der::integer(key_format as i64), authorization_list.to_der()])
+    }
+
+    pub fn to_der(&self) -> Vec<u8> {
+        der::sequence(&[
+            der::integer(VERSION),
+            der::octet_string(&self.encrypted_transport_key),
+            der::octet_string(&self.initialization_vector),
+            Self::key_description(self.key_format, &self.authorization_list),
+            der::octet_string(&self.encrypted_key),
+            der::octet_string(&self.tag),
+        ])
+    }
+}
+
+/// RSA-OAEP parameters the transport key was encrypted with
+///
+/// KeyMint requires SHA-256 as the OAEP digest, the MGF1 digest is usually SHA-1.
+pub struct OaepSpec {
+    pub digest: Digest,
+    pub mgf1_digest: Digest,
+}
+
+impl Default for OaepSpec {
+    fn default() -> Self {
+        Self {
+            digest: Digest::Sha256,
+            mgf1_digest: Digest::Sha1,
+        }
+    }
+}
+
+impl OaepSpec {
+    /// A new `javax.crypto.spec.OAEPParameterSpec`
+    fn to_java<'a>(&self, env: &mut JNIEnv<'a>) -> Result<JObject<'a>> {
+        static NEW_OAEP_PARAMETER_SPEC: Constructor = Constructor::new(
+            &OAEP_PARAMETER_SPEC,
+            "(Ljava/lang/String;Ljava/lang/String;Ljava/security/spec/AlgorithmParameterSpec;Ljavax/crypto/spec/PSource;)V",
+        );
+        static NEW_MGF1_PARAMETER_SPEC: Constructor =
+            Constructor::new(&MGF1_PARAMETER_SPEC, "(Ljava/lang/String;)V");
+
+        env.with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+            let digest = env.new_string(&self.digest)?;
+            let mgf1 = env.new_string("MGF1")?;
+            let mgf1_digest = env.new_string(&self.mgf1_digest)?;
+            let mgf1_spec =
+                NEW_MGF1_PARAMETER_SPEC.new_object(&[JValue::Object(&mgf1_digest)], env)?;
+
+            let pspecified = PSOURCE_PSPECIFIED.get(env)?;
+            let psource = env
+                .get_static_field(
+                    pspecified,
+                    "DEFAULT",
+                    "Ljavax/crypto/spec/PSource$PSpecified;",
+                )
+                .catch(env)?
+                .l()?;
+
+            NEW_OAEP_PARAMETER_SPEC.new_object(
+                &[
+                    JValue::Object(&digest),
+                    JValue::Object(&mgf1),
+                    JValue::Object(&mgf1_spec),
+                    JValue::Object(&psource),
+                ],
+                env,
+            )
+        })
+    }
+}
+
+java_class! {
+    WrappedKeyEntry(WRAPPED_KEY_ENTRY = "android/security/keystore/WrappedKeyEntry")
+}
+
+impl<'a> WrappedKeyEntry<'a> {
+    /// An entry holding a DER encoded [`SecureKeyWrapper`], to be unwrapped by the key
+    /// stored under `wrapping_alias`
+    pub fn new<S>(
+        wrapped: &[u8],
+        wrapping_alias: S,
+        spec: &OaepSpec,
+        env: &mut JNIEnv<'a>,
+    ) -> Result<Self>
+    where
+        S: Into<JNIString>,
+    {
+        static NEW: Constructor = Constructor::new(
+            &WRAPPED_KEY_ENTRY,
+            "([BLjava/lang/String;Ljava/lang/String;Ljava/security/spec/AlgorithmParameterSpec;)V",
+        );
+
+        api_level::require(api_level::P, env)?;
+
+        Ok(env
+            .with_local_frame_returning_local(LOCAL_FRAME_CAPACITY, |env| {
+                let wrapped = env.byte_array_from_slice(wrapped)?;
+                let wrapping_alias = env.new_string(wrapping_alias)?;
+                let transformation = env.new_string("RSA/ECB/OAEPPadding")?;
+                let spec = spec.to_java(env)?;
+
+                NEW.new_object(
+                    &[
+                        JValue::Object(&wrapped),
+                        JValue::Object(&wrapping_alias),
+                        JValue::Object(&transformation),
+                        JValue::Object(&spec),
+                    ],
+                    env,
+                )
+            })?
+            .wrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::der::tests::hex;
+
+    /// The AES-256 key description of the AOSP `ImportWrappedKeyTest` and KeyMint VTS vector
+    fn aes_authorization_list() -> AuthorizationList {
+        AuthorizationList {
+            purposes: vec![Purpose::Encrypt, Purpose::Decrypt],
+            algorithm: Some(KeyAlgorithm::Aes),
+            key_size: Some(256),
+            block_modes: vec![BlockMode::Ecb],
+            paddings: vec![Padding::Pkcs7],
+            no_auth_required: true,
+            ..Default::default()
+        }
+    }
+
+    const AES_KEY_DESCRIPTION: &str = "302e 020103 3029
+        a108 3106 020100 020101
+        a203 020120
+        a304 02020100
+        a405 3103 020101
+        a605 3103 020140
+        bf8377 02 0500";
+
+    #[test]
+    fn key_description_matches_aosp() {
+        let description =
+            SecureKeyWrapper::key_description(KeyFormat::Raw, &aes_authorization_list());
+        assert_eq!(description, hex(AES_KEY_DESCRIPTION));
+    }
+
+    #[test]
+    fn secure_key_wrapper() {
+        let wrapper = SecureKeyWrapper {
+            encrypted_transport_key: vec![0x11; 4],
+            initialization_vector: vec![0x22; 12],
+            key_format: KeyFormat::Raw,
+            authorization_list: aes_authorization_list(),
+            encrypted_key: vec![0x33; 32],
+            tag: vec![0x44; 16],
+        };
+
+        let expected = [
+            hex("307b 020100 0404"),
+            vec![0x11; 4],
+            hex("040c"),
+            vec![0x22; 12],
+            hex(AES_KEY_DESCRIPTION),
+            hex("0420"),
+            vec![0x33; 32],
+            hex("0410"),
+            vec![0x44; 16],
+        ]
+        .concat();
+        assert_eq!(wrapper.to_der(), expected);
+    }
+
+    #[test]
+    fn paddings_share_one_set() {
+        let list = AuthorizationList {
+            paddings: vec![Padding::RsaOaep],
+            signature_paddings: vec![SignaturePadding::Pss, SignaturePadding::Pkcs1],
+            ..Default::default()
+        };
+        assert_eq!(list.to_der(), hex("300d a60b 3109 020102 020103 020105"));
+    }
+
+    #[test]
+    fn large_rsa_public_exponent() {
+        let list = AuthorizationList {
+            rsa_public_exponent: Some(u64::MAX),
+            ..Default::default()
+        };
+        assert_eq!(list.to_der(), hex("300f bf8148 0b 0209 00ffffffffffffffff"));
+    }
+
+    #[test]
+    fn user_auth_types_are_a_bitmask() {
+        let list = AuthorizationList {
+            user_auth_types: vec![AuthType::DeviceCredential, AuthType::BiometricStrong],
+            auth_timeout: Some(30),
+            ..Default::default()
+        };
+        assert_eq!(list.to_der(), hex("300e bf8378 03 020103 bf8379 03 02011e"));
+    }
+}
@@ -0,0 +1,40 @@
+//! A desktop JVM for the tests and benches built with the `desktop-jvm` feature
+//!
+//! Needs `libjvm` at link and run time:
+//!
+//! ```text
+//! JVM=$JAVA_HOME/lib/server
+//! RUSTFLAGS="-L $JVM" LD_LIBRARY_PATH=$JVM cargo test --features desktop-jvm
+//! ```
+
+use std::ptr;
+
+use jni::{JavaVM, sys};
+
+#[link(name = "jvm")]
+unsafe extern "system" {}
+
+/// Creates the JVM of the process, which can only be done once
+pub fn create_vm() -> JavaVM {
+    let mut args = sys::JavaVMInitArgs {
+        version: sys::JNI_VERSION_1_8,
+        nOptions: 0,
+        options: ptr::null_mut(),
+        ignoreUnrecognized: sys::JNI_TRUE,
+    };
+    let mut vm = ptr::null_mut();
+    let mut env = ptr::null_mut();
+
+    // SAFETY: the arguments are valid for the duration of the call
+    let res = unsafe {
+        sys::JNI_CreateJavaVM(
+            &mut vm,
+            &mut env,
+            (&mut args as *mut sys::JavaVMInitArgs).cast(),
+        )
+    };
+    assert_eq!(res, sys::JNI_OK, "JNI_CreateJavaVM failed");
+
+    // SAFETY: `vm` was just created
+    unsafe { JavaVM::from_raw(vm).unwrap() }
+}
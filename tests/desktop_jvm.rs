@@ -0,0 +1,39 @@
+//! `init` and `with_jni_env` on a desktop JVM, without an Android context
+
+mod common;
+
+use std::thread;
+
+use android_keystore::{Error, SecretKey, SecretKeyAlgorithm, init, with_jni_env};
+use jni::JavaVM;
+
+#[test]
+fn init_without_context() {
+    // Neither init() nor ndk_context provided a JVM yet
+    assert!(matches!(
+        with_jni_env(|_, _| Ok(())),
+        Err(Error::NotInitialized)
+    ));
+
+    let vm = common::create_vm();
+    // SAFETY: the pointer comes from a live JavaVM
+    let second = unsafe { JavaVM::from_raw(vm.get_java_vm_pointer()).unwrap() };
+
+    init(vm, None).unwrap();
+    assert!(matches!(init(second, None), Err(Error::AlreadyInitialized)));
+
+    let algorithm = with_jni_env(|env, context| {
+        assert!(context.is_none());
+
+        let key = SecretKey::from_bytes(SecretKeyAlgorithm::Aes, &[0; 32], env)?;
+        key.get_algorithm(env)
+    })
+    .unwrap();
+    assert_eq!(algorithm, "AES");
+
+    // Other threads are attached on demand
+    let context = thread::spawn(|| with_jni_env(|_, context| Ok(context.is_some())))
+        .join()
+        .unwrap();
+    assert!(!context.unwrap());
+}